@@ -0,0 +1,319 @@
+use crate::json_parser::{
+    number_from_literal, parse_comment, parse_integer_part, parse_unicode_escape, ParseOptions,
+};
+use crate::JsonValue;
+use std::borrow::Cow;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while, take_while1, take_while_m_n},
+    character::complete::{char as char_, digit0, digit1, hex_digit1, one_of, satisfy},
+    combinator::{cut, map, map_opt, not, opt, recognize, value},
+    error::{context, ContextError, ErrorKind, ParseError},
+    multi::{fold_many0, many0_count},
+    sequence::{pair, preceded, terminated, tuple},
+    IResult,
+};
+
+/// ECMAScript whitespace, JSON's four chars plus the Unicode spaces
+fn is_json5_space(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t' | '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{A0}' | '\u{1680}' | '\u{2000}'
+            ..='\u{200A}'
+                | '\u{2028}'
+                | '\u{2029}'
+                | '\u{202F}'
+                | '\u{205F}'
+                | '\u{3000}'
+                | '\u{FEFF}'
+    )
+}
+
+/// JSON5 version of [`crate::json_parser::split`], also skips comments
+pub fn split_json5<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    recognize(many0_count(alt((
+        take_while1(is_json5_space),
+        parse_comment,
+    ))))(i)
+}
+
+/// a raw line break ends a string literal, it must be escaped to continue on the next line
+fn is_json5_literal_char(c: char, quote: char) -> bool {
+    c != quote && c != '\\' && c != '\n' && c != '\r'
+}
+
+/// the JSON escapes plus `\'`, `\v`, `\0`, `\xHH` and any other char standing for itself.
+/// A backslash before a line break is a line continuation which adds nothing to the string
+fn parse_json5_escape<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Option<char>, E> {
+    preceded(
+        char_('\\'),
+        cut(context(
+            "an escape sequence",
+            alt((
+                value(
+                    None,
+                    alt((
+                        tag("\r\n"),
+                        tag("\n"),
+                        tag("\r"),
+                        tag("\u{2028}"),
+                        tag("\u{2029}"),
+                    )),
+                ),
+                map(
+                    alt((
+                        value('\u{08}', char_('b')),
+                        value('\u{0C}', char_('f')),
+                        value('\n', char_('n')),
+                        value('\r', char_('r')),
+                        value('\t', char_('t')),
+                        value('\u{0B}', char_('v')),
+                        value(
+                            '\0',
+                            terminated(char_('0'), not(satisfy(|c| c.is_ascii_digit()))),
+                        ),
+                        preceded(
+                            char_('x'),
+                            map_opt(
+                                take_while_m_n(2, 2, |c: char| c.is_ascii_hexdigit()),
+                                |hex| u8::from_str_radix(hex, 16).ok().map(char::from),
+                            ),
+                        ),
+                        parse_unicode_escape,
+                        satisfy(|c| !c.is_ascii_digit() && c != 'x' && c != 'u'),
+                    )),
+                    Some,
+                ),
+            )),
+        )),
+    )(i)
+}
+
+enum Fragment<'a> {
+    Literal(&'a str),
+    Escaped(Option<char>),
+}
+
+fn parse_json5_quoted<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    quote: char,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Cow<'a, str>, E> {
+    move |i| {
+        let literal_char = move |c| is_json5_literal_char(c, quote);
+        let (rest, literal) = preceded(char_(quote), take_while(literal_char))(i)?;
+        if let Ok((rest, _)) = char_::<_, E>(quote)(rest) {
+            return Ok((rest, Cow::Borrowed(literal)));
+        }
+        map(
+            terminated(
+                fold_many0(
+                    alt((
+                        map(take_while1(literal_char), Fragment::Literal),
+                        map(parse_json5_escape, Fragment::Escaped),
+                    )),
+                    move || literal.to_string(),
+                    |mut string, fragment| {
+                        match fragment {
+                            Fragment::Literal(s) => string.push_str(s),
+                            Fragment::Escaped(Some(c)) => string.push(c),
+                            Fragment::Escaped(None) => {}
+                        }
+                        string
+                    },
+                ),
+                char_(quote),
+            ),
+            Cow::Owned,
+        )(rest)
+    }
+}
+
+/// a single or double quoted JSON5 string
+pub fn parse_json5_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    alt((parse_json5_quoted('"'), parse_json5_quoted('\'')))(i)
+}
+
+/// ECMAScript IdentifierName such as `$id`, `_private` or `名前`, `\u` escapes aren't supported
+fn parse_identifier<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(pair(
+        satisfy(|c| c.is_alphabetic() || c == '$' || c == '_'),
+        take_while(|c: char| {
+            c.is_alphanumeric() || matches!(c, '$' | '_' | '\u{200C}' | '\u{200D}')
+        }),
+    ))(i)
+}
+
+/// an object key is a string or an unquoted identifier
+pub fn parse_json5_key<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    alt((parse_json5_string, map(parse_identifier, Cow::Borrowed)))(i)
+}
+
+#[derive(Clone)]
+enum Json5Number<'a> {
+    /// `Infinity` or `NaN`
+    NonFinite(f64),
+    Hex(&'a str),
+    /// the literal rewritten without sign as JSON accepts it, e.g. `.5` is `0.5`
+    Decimal(String),
+}
+
+/// `[+-]? (Infinity | NaN | 0x hex digits | decimal)` where a decimal may start or end with `.`.
+/// Finite numbers are rewritten as a JSON literal so every [`crate::json_parser::NumberMode`]
+/// works, `Infinity` and `NaN` are always [`JsonValue::NumberF64`] and are written back as `null`
+pub fn parse_json5_number_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue<'a>, E> {
+    move |i| {
+        let (rest, sign) = opt(one_of("+-"))(i)?;
+        let negative = sign == Some('-');
+        let mut unsigned = alt((
+            value(Json5Number::NonFinite(f64::INFINITY), tag("Infinity")),
+            value(Json5Number::NonFinite(f64::NAN), tag("NaN")),
+            map(
+                preceded(
+                    alt((tag("0x"), tag("0X"))),
+                    cut(context("a hex digit", hex_digit1)),
+                ),
+                Json5Number::Hex,
+            ),
+            map(
+                pair(
+                    alt((
+                        pair(parse_integer_part, opt(preceded(char_('.'), digit0))),
+                        map(preceded(char_('.'), digit1), |fraction| {
+                            ("0", Some(fraction))
+                        }),
+                    )),
+                    opt(recognize(tuple((
+                        one_of("eE"),
+                        opt(one_of("+-")),
+                        cut(context("a digit", digit1)),
+                    )))),
+                ),
+                |((integer, fraction), exponent)| {
+                    let mut literal = integer.to_string();
+                    if let Some(fraction) = fraction.filter(|f| !f.is_empty()) {
+                        literal.push('.');
+                        literal.push_str(fraction);
+                    }
+                    literal.push_str(exponent.unwrap_or_default());
+                    Json5Number::Decimal(literal)
+                },
+            ),
+        ));
+        let (rest, number) = match sign {
+            Some(_) => cut(context("a number", unsigned))(rest)?,
+            None => unsigned(rest)?,
+        };
+        let literal = match number {
+            Json5Number::NonFinite(n) if negative => return Ok((rest, JsonValue::NumberF64(-n))),
+            Json5Number::NonFinite(n) => return Ok((rest, JsonValue::NumberF64(n))),
+            Json5Number::Hex(hex) => match u128::from_str_radix(hex, 16) {
+                Ok(n) => n.to_string(),
+                Err(_) => {
+                    return Err(nom::Err::Failure(E::add_context(
+                        i,
+                        "a hex number within 128 bits",
+                        E::from_error_kind(i, ErrorKind::HexDigit),
+                    )))
+                }
+            },
+            Json5Number::Decimal(literal) => literal,
+        };
+        let literal = if negative {
+            format!("-{}", literal)
+        } else {
+            literal
+        };
+        match number_from_literal(&literal, options.number_mode) {
+            Some(value) => Ok((rest, value)),
+            None => Err(nom::Err::Failure(E::add_context(
+                i,
+                "a number in f64 range",
+                E::from_error_kind(i, ErrorKind::Float),
+            ))),
+        }
+    }
+}
+
+#[test]
+fn test_json5() {
+    use crate::json_parser::{Dialect, NumberMode};
+
+    let json5 = ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    };
+    // the example of https://json5.org
+    let text = r#"// comments
+{
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON", /* block
+  comment */
+}
+"#;
+    assert!(JsonValue::from_str(text).is_err());
+    let value = JsonValue::from_str_with(text, json5).unwrap();
+    assert_eq!(
+        value.to_string(),
+        concat!(
+            r#"{"unquoted":"and you can quote me on that","#,
+            r#""singleQuotes":"I can use \"double quotes\" here","#,
+            r#""lineBreaks":"Look, Mom! No \\n's!","hexadecimal":912559,"#,
+            r#""leadingDecimalPoint":0.8675309,"andTrailing":8675309,"positiveSign":1,"#,
+            r#""trailingComma":"in objects","andIn":["arrays"],"backwardsCompatible":"with JSON"}"#
+        )
+    );
+
+    let parse = |text| JsonValue::from_str_with(text, json5);
+    assert_eq!(
+        parse("[Infinity, -Infinity, -0x10, '\\x41\\'\\v\\0\\q', 1e3, -.5e-1]").unwrap(),
+        JsonValue::Array(vec![
+            JsonValue::NumberF64(f64::INFINITY),
+            JsonValue::NumberF64(f64::NEG_INFINITY),
+            JsonValue::NumberI64(-16),
+            JsonValue::String("A'\u{0B}\0q".into()),
+            JsonValue::NumberF64(1000.0),
+            JsonValue::NumberF64(-0.05),
+        ])
+    );
+    assert!(matches!(parse("NaN"), Ok(JsonValue::NumberF64(n)) if n.is_nan()));
+    let precise = ParseOptions {
+        number_mode: NumberMode::ArbitraryPrecision,
+        ..json5
+    };
+    assert_eq!(
+        JsonValue::from_str_with("[0xFFFFFFFFFFFFFFFFFF, +.5]", precise)
+            .unwrap()
+            .to_string(),
+        "[4722366482869645213695,0.5]"
+    );
+
+    let error = |text| {
+        let e = parse(text).unwrap_err();
+        format!("{}:{} {}", e.line(), e.column(), e.expected().unwrap())
+    };
+    assert_eq!(error("[1,,]"), "1:4 a JSON value");
+    assert_eq!(error("{a: 1,,}"), "1:7 a key");
+    assert_eq!(error("[0x]"), "1:4 a hex digit");
+    assert_eq!(error("[01]"), "1:3 `,` or `]`");
+    assert_eq!(error("[+]"), "1:3 a number");
+    assert_eq!(error("'\\1'"), "1:3 an escape sequence");
+    assert_eq!(error("[1] /* open"), "1:7 `*/`");
+    assert_eq!(error("'a\nb'"), "1:3 `'`");
+}
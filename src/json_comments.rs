@@ -0,0 +1,203 @@
+use crate::json5::parse_json5_string;
+use crate::json_error::JsonError;
+use crate::json_parser::{parse_comment, parse_string, Dialect, ParseOptions};
+use crate::json_pointer::JsonPointer;
+use crate::json_spanned::{SpannedKind, SpannedValue};
+use crate::JsonValue;
+use std::borrow::Cow;
+
+/// where a comment goes back when the value is written again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommentAnchor {
+    /// on the lines before the object member or array item at the pointer, the root for `""`
+    Before(JsonPointer),
+    /// at the end of the line of the member or item at the pointer
+    After(JsonPointer),
+    /// after the last member or item of the object or array at the pointer
+    End(JsonPointer),
+}
+
+/// a comment of a JSONC or JSON5 document, see [`parse_with_comments`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment<'a> {
+    /// the comment with its delimiters, e.g. `// note` or `/* note */`
+    pub text: Cow<'a, str>,
+    pub anchor: CommentAnchor,
+}
+
+enum Mark {
+    Start,
+    Finish,
+    Close,
+}
+
+/// positions a comment can attach to, in document order
+fn collect_marks(
+    node: &SpannedValue<'_>,
+    path: &mut JsonPointer,
+    marks: &mut Vec<(usize, Mark, JsonPointer)>,
+) {
+    let members: Box<dyn Iterator<Item = (String, usize, &SpannedValue<'_>)>> = match &node.kind {
+        SpannedKind::Scalar(_) => return,
+        SpannedKind::Map(entries) => Box::new(
+            entries
+                .iter()
+                .map(|(k, v)| (k.key.to_string(), k.span.start.offset, v)),
+        ),
+        SpannedKind::Array(items) => Box::new(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v.span.start.offset, v)),
+        ),
+    };
+    for (token, start, value) in members {
+        path.push(token);
+        marks.push((start, Mark::Start, path.clone()));
+        collect_marks(value, path, marks);
+        marks.push((value.span.end.offset, Mark::Finish, path.clone()));
+        path.pop();
+    }
+    marks.push((node.span.end.offset - 1, Mark::Close, path.clone()));
+}
+
+/// `//` and `/* */` comments with their offset, strings are skipped so a `//` inside a string
+/// isn't taken as a comment. `input` must be a valid document of the dialect
+fn find_comments(input: &str, dialect: Dialect) -> Vec<(usize, &str)> {
+    let mut comments = Vec::new();
+    let mut rest = input;
+    while let Some(pos) = rest.find(['"', '\'', '/']) {
+        let at = &rest[pos..];
+        rest = match at.chars().next() {
+            Some('/') => {
+                let (after, comment) = parse_comment::<()>(at).expect("a valid document");
+                comments.push((input.len() - at.len(), comment));
+                after
+            }
+            _ if dialect == Dialect::Json5 => {
+                parse_json5_string::<()>(at).map_or(&at[1..], |r| r.0)
+            }
+            _ => parse_string::<()>(at).map_or(&at[1..], |r| r.0),
+        };
+    }
+    comments
+}
+
+/// Parse a JSONC or JSON5 document (per `options.dialect`) and keep its comments so
+/// [`JsonValue::to_string_pretty_with_comments`] can write them back.
+///
+/// A comment on the same line after a member stays after it, a comment on its own line goes
+/// before the next member or before the closing bracket when it is the last thing of a container
+pub fn parse_with_comments(
+    input: &str,
+    options: ParseOptions,
+) -> Result<(JsonValue<'_>, Vec<Comment<'_>>), JsonError> {
+    let root = SpannedValue::from_str_with(input, options)?;
+    let mut marks = vec![(root.span.start.offset, Mark::Start, JsonPointer::root())];
+    collect_marks(&root, &mut JsonPointer::root(), &mut marks);
+    marks.push((root.span.end.offset, Mark::Finish, JsonPointer::root()));
+
+    let comments = find_comments(input, options.dialect)
+        .into_iter()
+        .map(|(offset, text)| {
+            let next = marks.partition_point(|(at, _, _)| *at < offset + text.len());
+            let anchor = match (next.checked_sub(1).map(|i| &marks[i]), marks.get(next)) {
+                (Some((at, Mark::Finish, path)), _) if !input[*at..offset].contains('\n') => {
+                    CommentAnchor::After(path.clone())
+                }
+                (_, Some((_, Mark::Close, path))) => CommentAnchor::End(path.clone()),
+                // a comment inside a member, e.g. between the key and the value, goes before it
+                (_, Some((_, Mark::Start | Mark::Finish, path))) => {
+                    CommentAnchor::Before(path.clone())
+                }
+                (_, None) => CommentAnchor::After(JsonPointer::root()),
+            };
+            Comment {
+                text: Cow::Borrowed(text),
+                anchor,
+            }
+        })
+        .collect();
+    Ok((root.into_value(), comments))
+}
+
+#[test]
+fn test_parse_with_comments() {
+    let jsonc = ParseOptions {
+        dialect: Dialect::Jsonc,
+        ..ParseOptions::default()
+    };
+    let text = r#"// settings of the editor
+{
+    // font
+    "editor.fontSize": 14, // pixels
+    "files.exclude": {
+        "**/.git": true, /* vcs */
+        // more to come
+    },
+    "url": "http://example.com/*not a comment*/",
+    "list": [1, /* two */ 2,],
+} // end
+"#;
+    assert!(JsonValue::from_str(text).is_err());
+    let (value, comments) = parse_with_comments(text, jsonc).unwrap();
+    assert_eq!(value, JsonValue::from_str_with(text, jsonc).unwrap());
+    let pointer = |p: &str| p.parse::<JsonPointer>().unwrap();
+    assert_eq!(
+        comments
+            .iter()
+            .map(|c| (c.text.as_ref(), c.anchor.clone()))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "// settings of the editor",
+                CommentAnchor::Before(pointer(""))
+            ),
+            (
+                "// font",
+                CommentAnchor::Before(pointer("/editor.fontSize"))
+            ),
+            (
+                "// pixels",
+                CommentAnchor::After(pointer("/editor.fontSize"))
+            ),
+            (
+                "/* vcs */",
+                CommentAnchor::After(pointer("/files.exclude/**~1.git"))
+            ),
+            (
+                "// more to come",
+                CommentAnchor::End(pointer("/files.exclude"))
+            ),
+            ("/* two */", CommentAnchor::After(pointer("/list/0"))),
+            ("// end", CommentAnchor::After(pointer(""))),
+        ]
+    );
+    assert_eq!(
+        value.to_string_pretty_with_comments(2, &comments),
+        r#"// settings of the editor
+{
+  // font
+  "editor.fontSize": 14, // pixels
+  "files.exclude": {
+    "**/.git": true /* vcs */
+    // more to come
+  },
+  "url": "http://example.com/*not a comment*/",
+  "list": [
+    1, /* two */
+    2
+  ]
+} // end"#
+    );
+
+    let error = JsonValue::from_str_with("[1, /* open", jsonc).unwrap_err();
+    assert_eq!((error.column(), error.expected()), (7, Some("`*/`")));
+    // JSON5 single quoted strings can hold `//` too
+    let json5 = ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    };
+    let (_, comments) = parse_with_comments("{a: '//', b: 1 /* b */}", json5).unwrap();
+    assert_eq!(comments[0].anchor, CommentAnchor::After(pointer("/b")));
+}
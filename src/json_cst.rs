@@ -0,0 +1,738 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_map::{dedupe_entries, JsonMap};
+use crate::json_parser::{
+    build_json_document, duplicate_key, DuplicateKeyPolicy, JsonBuilder, ParseOptions, Trivia,
+};
+use crate::json_pointer::{JsonPointer, PointerError};
+use crate::json_serializer::write_string;
+use crate::JsonValue;
+use nom::{
+    combinator::eof,
+    error::{context, ContextError, ParseError},
+};
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+/// a node with the text it was read from. The trivia fields hold the whitespace and comments
+/// between tokens, the containers keep the trivia before their closing bracket in `close`
+#[derive(Debug, Clone)]
+enum Node<'a> {
+    Scalar {
+        /// the original spelling, e.g. `1.50`, `"é"` or JSON5 `0x1F`
+        raw: Cow<'a, str>,
+        value: JsonValue<'a>,
+    },
+    Array {
+        items: Vec<Item<'a>>,
+        close: Cow<'a, str>,
+    },
+    Map {
+        members: Vec<Member<'a>>,
+        close: Cow<'a, str>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Item<'a> {
+    before: Cow<'a, str>,
+    value: Node<'a>,
+    /// trivia between the value and its `,`, empty for the last item without one
+    after: Cow<'a, str>,
+    comma: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Member<'a> {
+    before: Cow<'a, str>,
+    raw_key: Cow<'a, str>,
+    key: Cow<'a, str>,
+    before_colon: Cow<'a, str>,
+    after_colon: Cow<'a, str>,
+    value: Node<'a>,
+    after: Cow<'a, str>,
+    comma: bool,
+}
+
+/// the trivia and comma shared by array items and object members
+trait Entry<'a> {
+    fn layout(&mut self) -> (&mut Cow<'a, str>, &mut Cow<'a, str>, &mut bool);
+}
+
+impl<'a> Entry<'a> for Item<'a> {
+    fn layout(&mut self) -> (&mut Cow<'a, str>, &mut Cow<'a, str>, &mut bool) {
+        (&mut self.before, &mut self.after, &mut self.comma)
+    }
+}
+
+impl<'a> Entry<'a> for Member<'a> {
+    fn layout(&mut self) -> (&mut Cow<'a, str>, &mut Cow<'a, str>, &mut bool) {
+        (&mut self.before, &mut self.after, &mut self.comma)
+    }
+}
+
+/// insert `entry` at `at` with the trivia of its neighbours, so a new line in a multi-line
+/// container gets the same indentation as the others and a compact one stays compact
+fn insert_entry<'a, T: Entry<'a>>(entries: &mut Vec<T>, at: usize, mut entry: T) {
+    let len = entries.len();
+    if len == 0 {
+        entries.push(entry);
+        return;
+    }
+    // the first entry may be laid out differently, e.g. `[1, 2]`, take the others as model
+    let separator = entries[len.min(2) - 1].layout().0.clone();
+    let between = match len {
+        1 => Cow::Borrowed(""),
+        _ => entries[0].layout().1.clone(),
+    };
+    let (before, after, comma) = entry.layout();
+    if at == len {
+        // the new entry is the last one and takes over the end of the previous last one
+        let (_, last_after, last_comma) = entries[len - 1].layout();
+        *after = std::mem::replace(last_after, between);
+        *comma = std::mem::replace(last_comma, true);
+        *before = separator;
+    } else {
+        let (next_before, _, _) = entries[at].layout();
+        *before = next_before.clone();
+        if at == 0 {
+            *next_before = separator;
+        }
+        *after = between;
+        *comma = true;
+    }
+    entries.insert(at, entry);
+}
+
+/// remove the entry at `at`, the trivia that belongs to its position goes to a neighbour
+fn remove_entry<'a, T: Entry<'a>>(entries: &mut Vec<T>, at: usize, close: &mut Cow<'a, str>) -> T {
+    let mut removed = entries.remove(at);
+    let (before, after, comma) = removed.layout();
+    if entries.is_empty() {
+        // keep the comments before the closing bracket
+        if close.trim().is_empty() {
+            *close = Cow::Borrowed("");
+        }
+    } else if at == entries.len() {
+        let (_, last_after, last_comma) = entries[at - 1].layout();
+        *last_after = std::mem::take(after);
+        *last_comma = *comma;
+    } else if at == 0 {
+        *entries[0].layout().0 = std::mem::take(before);
+    }
+    removed
+}
+
+impl<'a> Member<'a> {
+    /// a member laid out like `model`, the member it will follow
+    fn new(key: &str, value: JsonValue<'a>, model: Option<&Member<'a>>) -> Self {
+        let mut raw_key = String::new();
+        write_string(&mut raw_key, key).expect("writing to a String never fails");
+        let (before_colon, after_colon) = match model {
+            Some(m) => (m.before_colon.clone(), m.after_colon.clone()),
+            None => (Cow::Borrowed(""), Cow::Borrowed("")),
+        };
+        Member {
+            before: Cow::Borrowed(""),
+            raw_key: Cow::Owned(raw_key),
+            key: Cow::Owned(key.to_string()),
+            before_colon,
+            after_colon,
+            value: Node::from_value(value),
+            after: Cow::Borrowed(""),
+            comma: false,
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    /// a compact node for a value that wasn't read from the document
+    fn from_value(value: JsonValue<'a>) -> Self {
+        match value {
+            JsonValue::Array(array) => {
+                let len = array.len();
+                let items = array
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| Item {
+                        before: Cow::Borrowed(""),
+                        value: Self::from_value(value),
+                        after: Cow::Borrowed(""),
+                        comma: i + 1 < len,
+                    })
+                    .collect();
+                Node::Array {
+                    items,
+                    close: Cow::Borrowed(""),
+                }
+            }
+            JsonValue::Map(map) => {
+                let len = map.len();
+                let members = map
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (key, value))| {
+                        let mut raw_key = String::new();
+                        write_string(&mut raw_key, &key).expect("writing to a String never fails");
+                        Member {
+                            before: Cow::Borrowed(""),
+                            raw_key: Cow::Owned(raw_key),
+                            key,
+                            before_colon: Cow::Borrowed(""),
+                            after_colon: Cow::Borrowed(""),
+                            value: Self::from_value(value),
+                            after: Cow::Borrowed(""),
+                            comma: i + 1 < len,
+                        }
+                    })
+                    .collect();
+                Node::Map {
+                    members,
+                    close: Cow::Borrowed(""),
+                }
+            }
+            value => Node::Scalar {
+                raw: Cow::Owned(value.to_string()),
+                value,
+            },
+        }
+    }
+
+    fn to_value(&self, policy: DuplicateKeyPolicy) -> JsonValue<'a> {
+        match self {
+            Node::Scalar { value, .. } => value.clone(),
+            Node::Array { items, .. } => {
+                JsonValue::Array(items.iter().map(|i| i.value.to_value(policy)).collect())
+            }
+            Node::Map { members, .. } => {
+                let entries = members
+                    .iter()
+                    .map(|m| (m.key.clone(), m.value.to_value(policy)))
+                    .collect();
+                // the Error policy already rejected duplicates while parsing
+                let map = JsonMap::from_entries_with(entries, policy).expect("no duplicate key");
+                JsonValue::Map(map)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comma = |comma| if comma { "," } else { "" };
+        match self {
+            Node::Scalar { raw, .. } => f.write_str(raw),
+            Node::Array { items, close } => {
+                f.write_char('[')?;
+                for item in items {
+                    let (before, after) = (&item.before, &item.after);
+                    write!(f, "{}{}{}{}", before, item.value, after, comma(item.comma))?;
+                }
+                write!(f, "{}]", close)
+            }
+            Node::Map { members, close } => {
+                f.write_char('{')?;
+                for m in members {
+                    write!(
+                        f,
+                        "{}{}{}:{}{}{}{}",
+                        m.before,
+                        m.raw_key,
+                        m.before_colon,
+                        m.after_colon,
+                        m.value,
+                        m.after,
+                        comma(m.comma)
+                    )?;
+                }
+                write!(f, "{}}}", close)
+            }
+        }
+    }
+}
+
+/// the text consumed between `i` and `rest`
+fn consumed<'a>(i: &'a str, rest: &str) -> &'a str {
+    &i[..i.len() - rest.len()]
+}
+
+/// builds the lossless tree, every member is kept and the policy only decides which value a
+/// lookup sees
+struct CstBuilder {
+    policy: DuplicateKeyPolicy,
+}
+
+impl<'a, E: ParseError<&'a str> + ContextError<&'a str>> JsonBuilder<'a, E> for CstBuilder {
+    type Value = Node<'a>;
+    type Array = Vec<Item<'a>>;
+    /// the members and where their keys start, to point at a duplicate
+    type Map = (Vec<Member<'a>>, Vec<&'a str>);
+    /// where the key starts, its spelling and its value
+    type Key = (&'a str, &'a str, Cow<'a, str>);
+
+    fn scalar(&mut self, start: &'a str, value: JsonValue<'a>, rest: &'a str) -> Self::Value {
+        let raw = Cow::Borrowed(consumed(start, rest));
+        Node::Scalar { raw, value }
+    }
+
+    fn start_array(&mut self, _start: &'a str) -> Self::Array {
+        Vec::new()
+    }
+
+    fn item(&mut self, items: &mut Self::Array, value: Self::Value, trivia: Trivia<'a>) {
+        items.push(Item {
+            before: Cow::Borrowed(trivia.before),
+            value,
+            after: Cow::Borrowed(trivia.after),
+            comma: trivia.comma,
+        });
+    }
+
+    fn end_array(&mut self, items: Self::Array, close: &'a str, _rest: &'a str) -> Self::Value {
+        let close = Cow::Borrowed(close);
+        Node::Array { items, close }
+    }
+
+    fn start_map(&mut self, _start: &'a str) -> Self::Map {
+        (Vec::new(), Vec::new())
+    }
+
+    fn key(
+        &mut self,
+        _map: &mut Self::Map,
+        start: &'a str,
+        key: Cow<'a, str>,
+        rest: &'a str,
+    ) -> Result<Self::Key, nom::Err<E>> {
+        Ok((start, consumed(start, rest), key))
+    }
+
+    fn member(&mut self, map: &mut Self::Map, key: Self::Key, value: Self::Value, t: Trivia<'a>) {
+        let (start, raw_key, key) = key;
+        map.0.push(Member {
+            before: Cow::Borrowed(t.before),
+            raw_key: Cow::Borrowed(raw_key),
+            key,
+            before_colon: Cow::Borrowed(t.before_colon),
+            after_colon: Cow::Borrowed(t.after_colon),
+            value,
+            after: Cow::Borrowed(t.after),
+            comma: t.comma,
+        });
+        map.1.push(start);
+    }
+
+    fn end_map(
+        &mut self,
+        (members, positions): Self::Map,
+        close: &'a str,
+        _rest: &'a str,
+    ) -> Result<Self::Value, nom::Err<E>> {
+        if self.policy == DuplicateKeyPolicy::Error {
+            let keys = members.iter().map(|m| (m.key.as_ref(), ())).collect();
+            if let Err(duplicate) = dedupe_entries(keys, DuplicateKeyPolicy::Error) {
+                return Err(duplicate_key(positions[duplicate]));
+            }
+        }
+        let close = Cow::Borrowed(close);
+        Ok(Node::Map { members, close })
+    }
+}
+
+/// A lossless syntax tree of a document: whitespace, comments and the spelling of every key
+/// and scalar are kept, so printing it gives back the input byte for byte.
+///
+/// Meant for tools that edit a config file in place, e.g. bumping the version of a
+/// `package.json` without reformatting the rest of it
+#[derive(Debug, Clone)]
+pub struct CstDocument<'a> {
+    before: Cow<'a, str>,
+    root: Node<'a>,
+    after: Cow<'a, str>,
+    options: ParseOptions,
+}
+
+impl<'a> CstDocument<'a> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'a str) -> Result<Self, JsonError> {
+        Self::from_str_with(s, ParseOptions::default())
+    }
+
+    /// accepts the same documents as [`JsonValue::from_str_with`] and fails with the same error
+    pub fn from_str_with(s: &'a str, options: ParseOptions) -> Result<Self, JsonError> {
+        let mut builder = CstBuilder {
+            policy: options.duplicate_key_policy,
+        };
+        let (rest, (before, root, after)) =
+            build_json_document::<JsonParseError, _>(s, options, &mut builder)
+                .map_err(|e| JsonError::from_nom(s, e))?;
+        context("end of input", eof)(rest).map_err(|e| JsonError::from_nom(s, e))?;
+        Ok(Self {
+            before: Cow::Borrowed(before),
+            root,
+            after: Cow::Borrowed(after),
+            options,
+        })
+    }
+
+    /// the value of the document, with duplicate keys resolved by the parse options
+    pub fn to_value(&self) -> JsonValue<'a> {
+        self.root.to_value(self.options.duplicate_key_policy)
+    }
+
+    /// the value at a JSON Pointer
+    pub fn get(&self, pointer: &str) -> Result<JsonValue<'a>, PointerError> {
+        let node = self.node(&pointer.parse()?)?;
+        Ok(node.to_value(self.options.duplicate_key_policy))
+    }
+
+    /// the source text of the node at a JSON Pointer, without the trivia around it
+    pub fn raw(&self, pointer: &str) -> Result<String, PointerError> {
+        Ok(self.node(&pointer.parse()?)?.to_string())
+    }
+
+    /// Replace the node at a JSON Pointer, everything around it is printed as before.
+    ///
+    /// The new value is written compact. With repeated keys the member replaced is the one
+    /// whose value the duplicate key policy keeps
+    pub fn set(&mut self, pointer: &str, value: JsonValue<'a>) -> Result<(), PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        *self.node_mut(&pointer, pointer.tokens().len())? = Node::from_value(value);
+        Ok(())
+    }
+
+    /// JSON Patch `add`: set an object member, insert into an array shifting the following
+    /// items, `-` append to an array, the root pointer replace the whole document.
+    ///
+    /// A new key is appended to its object. The new entry is written compact and copies the
+    /// indentation and the spacing around `:` and `,` of its neighbours
+    pub fn insert(&mut self, pointer: &str, value: JsonValue<'a>) -> Result<(), PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        let Some(last) = pointer.tokens().len().checked_sub(1) else {
+            self.root = Node::from_value(value);
+            return Ok(());
+        };
+        let policy = self.options.duplicate_key_policy;
+        let key = &pointer.tokens()[last];
+        match self.node_mut(&pointer, last)? {
+            Node::Map { members, .. } => match find_member(members, key, policy) {
+                Some(at) => members[at].value = Node::from_value(value),
+                None => {
+                    let member = Member::new(key, value, members.last());
+                    insert_entry(members, members.len(), member);
+                }
+            },
+            Node::Array { items, .. } => {
+                let at = pointer.index(last, items.len(), true)?;
+                let item = Item {
+                    before: Cow::Borrowed(""),
+                    value: Node::from_value(value),
+                    after: Cow::Borrowed(""),
+                    comma: false,
+                };
+                insert_entry(items, at, item);
+            }
+            Node::Scalar { .. } => return Err(not_a_container(&pointer, last)),
+        }
+        Ok(())
+    }
+
+    /// Remove the target from its parent and return its value, the root pointer leave `null` in
+    /// place of the document.
+    ///
+    /// Every member with the key is removed, the returned value is the one a lookup sees. The
+    /// comma and the trivia before the closing bracket move to the new last entry
+    pub fn remove(&mut self, pointer: &str) -> Result<JsonValue<'a>, PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        let policy = self.options.duplicate_key_policy;
+        let Some(last) = pointer.tokens().len().checked_sub(1) else {
+            let root = std::mem::replace(&mut self.root, Node::from_value(JsonValue::Null));
+            return Ok(root.to_value(policy));
+        };
+        let key = &pointer.tokens()[last];
+        match self.node_mut(&pointer, last)? {
+            Node::Map { members, close } => {
+                let at =
+                    find_member(members, key, policy).ok_or_else(|| missing_key(&pointer, last))?;
+                let value = members[at].value.to_value(policy);
+                while let Some(at) = members.iter().position(|m| m.key == *key) {
+                    remove_entry(members, at, close);
+                }
+                Ok(value)
+            }
+            Node::Array { items, close } => {
+                let at = pointer.index(last, items.len(), false)?;
+                Ok(remove_entry(items, at, close).value.to_value(policy))
+            }
+            Node::Scalar { .. } => Err(not_a_container(&pointer, last)),
+        }
+    }
+
+    /// the node at the first `depth` tokens of `pointer`
+    fn node_mut(
+        &mut self,
+        pointer: &JsonPointer,
+        depth: usize,
+    ) -> Result<&mut Node<'a>, PointerError> {
+        let policy = self.options.duplicate_key_policy;
+        let mut node = &mut self.root;
+        for (depth, token) in pointer.tokens()[..depth].iter().enumerate() {
+            node = match node {
+                Node::Map { members, .. } => {
+                    let at = find_member(members, token, policy)
+                        .ok_or_else(|| missing_key(pointer, depth))?;
+                    &mut members[at].value
+                }
+                Node::Array { items, .. } => {
+                    let at = pointer.index(depth, items.len(), false)?;
+                    &mut items[at].value
+                }
+                Node::Scalar { .. } => return Err(not_a_container(pointer, depth)),
+            };
+        }
+        Ok(node)
+    }
+
+    fn node(&self, pointer: &JsonPointer) -> Result<&Node<'a>, PointerError> {
+        let policy = self.options.duplicate_key_policy;
+        let mut node = &self.root;
+        for (depth, token) in pointer.tokens().iter().enumerate() {
+            node = match node {
+                Node::Map { members, .. } => {
+                    let at = find_member(members, token, policy)
+                        .ok_or_else(|| missing_key(pointer, depth))?;
+                    &members[at].value
+                }
+                Node::Array { items, .. } => {
+                    &items[pointer.index(depth, items.len(), false)?].value
+                }
+                Node::Scalar { .. } => return Err(not_a_container(pointer, depth)),
+            };
+        }
+        Ok(node)
+    }
+}
+
+/// the member a lookup sees, the last one with the key for LastWins and the first otherwise
+fn find_member(members: &[Member<'_>], key: &str, policy: DuplicateKeyPolicy) -> Option<usize> {
+    let mut matching = members.iter().enumerate().filter(|(_, m)| m.key == key);
+    let found = match policy {
+        DuplicateKeyPolicy::LastWins => matching.next_back(),
+        _ => matching.next(),
+    };
+    found.map(|(at, _)| at)
+}
+
+fn missing_key(pointer: &JsonPointer, depth: usize) -> PointerError {
+    PointerError::MissingKey {
+        pointer: pointer.prefix(depth),
+    }
+}
+
+fn not_a_container(pointer: &JsonPointer, depth: usize) -> PointerError {
+    PointerError::NotAContainer {
+        pointer: pointer.prefix(depth),
+    }
+}
+
+impl fmt::Display for CstDocument<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.before, self.root, self.after)
+    }
+}
+
+#[test]
+fn test_json_cst() {
+    use crate::json_parser::{Dialect, RootMode};
+
+    let jsonc = ParseOptions {
+        dialect: Dialect::Jsonc,
+        ..ParseOptions::default()
+    };
+    let text = r#"// generated, edit with care
+{
+    "name" :  "demo",
+    "version": "1.2.3", // bumped by the release script
+    "price": 1.50,
+    "escaped": "café",
+    "scripts": {
+        "test": "cargo test"   ,
+        /* none yet */
+    },
+    "files": [ ],
+}
+"#;
+    let mut document = CstDocument::from_str_with(text, jsonc).unwrap();
+    assert_eq!(document.to_string(), text);
+    assert_eq!(
+        document.to_value(),
+        JsonValue::from_str_with(text, jsonc).unwrap()
+    );
+    assert_eq!(document.raw("/price").unwrap(), "1.50");
+    assert_eq!(document.raw("/escaped").unwrap(), r#""café""#);
+    assert_eq!(
+        document.get("/escaped").unwrap(),
+        JsonValue::String("café".into())
+    );
+
+    document
+        .set("/version", JsonValue::String("1.3.0".into()))
+        .unwrap();
+    assert_eq!(
+        document.to_string(),
+        text.replace(r#""1.2.3""#, r#""1.3.0""#)
+    );
+    // new containers are written compact
+    document
+        .set(
+            "/files",
+            JsonValue::from_str(r#"["a", {"b" : 1}]"#).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        document.to_string(),
+        text.replace(r#""1.2.3""#, r#""1.3.0""#)
+            .replace("[ ]", r#"["a",{"b":1}]"#)
+    );
+    assert_eq!(document.raw("/files/1").unwrap(), r#"{"b":1}"#);
+    assert_eq!(
+        document.set("/price/0", JsonValue::Null),
+        Err(PointerError::NotAContainer {
+            pointer: "/price/0".to_string()
+        })
+    );
+    assert_eq!(
+        document.get("/nope"),
+        Err(PointerError::MissingKey {
+            pointer: "/nope".to_string()
+        })
+    );
+
+    // a lookup sees the member the duplicate key policy keeps
+    let mut document = CstDocument::from_str(r#"{"a": 1, "a": 2}"#).unwrap();
+    document.set("/a", JsonValue::NumberI64(3)).unwrap();
+    assert_eq!(document.to_string(), r#"{"a": 1, "a": 3}"#);
+
+    // new entries copy the layout of their neighbours, removed ones leave no gap
+    let mut document = CstDocument::from_str_with(text, jsonc).unwrap();
+    document
+        .insert("/license", JsonValue::String("MIT".into()))
+        .unwrap();
+    assert_eq!(
+        document.remove("/scripts/test").unwrap(),
+        JsonValue::String("cargo test".into())
+    );
+    assert_eq!(
+        document.to_string(),
+        text.replace(
+            "\"files\": [ ],\n",
+            "\"files\": [ ],\n    \"license\": \"MIT\",\n"
+        )
+        .replace("\"test\": \"cargo test\"   ,\n        ", "")
+    );
+    assert_eq!(
+        document.to_value(),
+        JsonValue::from_str_with(&document.to_string(), jsonc).unwrap()
+    );
+    for (text, edits, expected) in [
+        ("[1, 2, 3]", &[("/1", Some(0))][..], "[1, 0, 2, 3]"),
+        (
+            "[1, 2, 3]",
+            &[("/0", Some(0)), ("/-", Some(4))],
+            "[0, 1, 2, 3, 4]",
+        ),
+        ("[1, 2, 3]", &[("/2", None), ("/0", None)], "[2]"),
+        ("[1, 2, 3,]", &[("/2", None), ("/-", Some(5))], "[1, 2, 5,]"),
+        ("[\n  1\n]", &[("/-", Some(2))], "[\n  1,\n  2\n]"),
+        ("[\n  1,\n  2\n]", &[("/1", None)], "[\n  1\n]"),
+        ("[ 1 ]", &[("/0", None), ("/0", Some(2))], "[2]"),
+        (
+            "{}",
+            &[("/a", Some(1)), ("/b", Some(2))],
+            r#"{"a":1,"b":2}"#,
+        ),
+        (
+            "{\n  \"a\" : 1\n}",
+            &[("/b", Some(2))],
+            "{\n  \"a\" : 1,\n  \"b\" : 2\n}",
+        ),
+        (
+            r#"{"a": 1, "b": 2, "a": 3}"#,
+            &[("/a", None)],
+            r#"{"b": 2}"#,
+        ),
+        (
+            r#"{"a": 1, "b": 2}"#,
+            &[("/a", Some(3))],
+            r#"{"a": 3, "b": 2}"#,
+        ),
+    ] {
+        let mut document = CstDocument::from_str_with(text, jsonc).unwrap();
+        for (pointer, value) in edits {
+            match value {
+                Some(n) => document.insert(pointer, JsonValue::from(*n)).unwrap(),
+                None => drop(document.remove(pointer).unwrap()),
+            }
+        }
+        assert_eq!(document.to_string(), expected, "{}", text);
+    }
+    let mut document = CstDocument::from_str(r#"{"a": [1]}"#).unwrap();
+    assert_eq!(
+        document.remove("/b"),
+        Err(PointerError::MissingKey {
+            pointer: "/b".to_string()
+        })
+    );
+    assert_eq!(
+        document.insert("/a/0/x", JsonValue::Null),
+        Err(PointerError::NotAContainer {
+            pointer: "/a/0/x".to_string()
+        })
+    );
+    assert_eq!(document.remove("").unwrap().to_string(), r#"{"a":[1]}"#);
+    assert_eq!(document.to_string(), "null");
+
+    let json5 = ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    };
+    let text = "{unquoted: 0x1F, 'single': +.5, list: [Infinity,],}";
+    let document = CstDocument::from_str_with(text, json5).unwrap();
+    assert_eq!(document.to_string(), text);
+    assert_eq!(document.raw("/unquoted").unwrap(), "0x1F");
+    assert_eq!(
+        document.to_value(),
+        JsonValue::from_str_with(text, json5).unwrap()
+    );
+
+    // same errors as the plain tree
+    for input in [
+        r#"{"a": 1, "a": 2}"#,
+        "[1,\n 2,",
+        "[1,]",
+        "{\"a\" 1}",
+        "{,}",
+        "[1] x",
+        " 1 ",
+    ] {
+        for policy in [DuplicateKeyPolicy::LastWins, DuplicateKeyPolicy::Error] {
+            for root_mode in [RootMode::AnyValue, RootMode::Rfc4627] {
+                let options = ParseOptions {
+                    duplicate_key_policy: policy,
+                    root_mode,
+                    ..ParseOptions::default()
+                };
+                let document = CstDocument::from_str_with(input, options);
+                match JsonValue::from_str_with(input, options) {
+                    Ok(value) => {
+                        let document = document.unwrap();
+                        assert_eq!(
+                            (document.to_string(), document.to_value()),
+                            (input.into(), value)
+                        );
+                    }
+                    Err(error) => assert_eq!(document.unwrap_err(), error),
+                }
+            }
+        }
+    }
+}
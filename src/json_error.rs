@@ -0,0 +1,275 @@
+use nom::error::{ContextError, ErrorKind, ParseError};
+use std::fmt;
+
+/// how many chars around the error position are kept in [`JsonError::snippet`]
+const SNIPPET_RADIUS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    Char(char),
+    Context(&'static str),
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Char(c) => write!(f, "`{}`", c),
+            Expected::Context(context) => f.write_str(context),
+        }
+    }
+}
+
+/// nom error type for the generic `E` of json_parser, it remembers the furthest failure position
+/// and what the grammar expected there, then [`JsonError::new`] turns it into a readable error
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonParseError<'a> {
+    input: &'a str,
+    kind: ErrorKind,
+    expected: Vec<Expected>,
+    /// the innermost `context` at the failure position is the most precise one, outer contexts
+    /// at the same position must not overwrite it
+    from_context: bool,
+}
+
+impl<'a> ParseError<&'a str> for JsonParseError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        Self {
+            input,
+            kind,
+            expected: Vec::new(),
+            from_context: false,
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        Self {
+            input,
+            kind: ErrorKind::Char,
+            expected: vec![Expected::Char(c)],
+            from_context: false,
+        }
+    }
+
+    /// keep the alternative that went further, merge the expectations if they failed at the same place
+    fn or(mut self, other: Self) -> Self {
+        match self.input.len().cmp(&other.input.len()) {
+            std::cmp::Ordering::Less => self,
+            std::cmp::Ordering::Greater => other,
+            std::cmp::Ordering::Equal => {
+                for expected in other.expected {
+                    if !self.expected.contains(&expected) {
+                        self.expected.push(expected);
+                    }
+                }
+                self.from_context |= other.from_context;
+                self
+            }
+        }
+    }
+}
+
+impl<'a> ContextError<&'a str> for JsonParseError<'a> {
+    /// a context only describe the failure if the parser it wraps made no progress
+    fn add_context(input: &'a str, context: &'static str, mut other: Self) -> Self {
+        if other.input.len() == input.len() && !other.from_context {
+            other.expected = vec![Expected::Context(context)];
+            other.from_context = true;
+        }
+        other
+    }
+}
+
+/// a syntax error, or the I/O error that interrupted reading, with its position in the source document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub(crate) offset: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) expected: Option<String>,
+    pub(crate) found: Option<char>,
+    pub(crate) snippet: String,
+    /// kind and message of the I/O error that stopped reading, the other fields are then only
+    /// the position where it happened
+    pub(crate) io: Option<(std::io::ErrorKind, String)>,
+}
+
+impl JsonError {
+    /// `input` must be the whole document that `error` was produced from
+    pub fn new(input: &str, error: JsonParseError<'_>) -> Self {
+        let expected = if error.expected.is_empty() {
+            None
+        } else {
+            let items = error
+                .expected
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            Some(items.join(" or "))
+        };
+        Self::at(input, input.len() - error.input.len(), expected)
+    }
+
+    pub(crate) fn from_nom(input: &str, error: nom::Err<JsonParseError<'_>>) -> Self {
+        match error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => Self::new(input, e),
+            nom::Err::Incomplete(_) => Self::at(input, input.len(), Some("more input".to_string())),
+        }
+    }
+
+    pub(crate) fn at(input: &str, offset: usize, expected: Option<String>) -> Self {
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |pos| offset + pos);
+        let line_text = input[line_start..line_end].trim_end_matches('\r');
+        let column = before[line_start..].chars().count() + 1;
+
+        let chars = line_text.chars().collect::<Vec<_>>();
+        let start = (column - 1).saturating_sub(SNIPPET_RADIUS);
+        let end = chars.len().min(column - 1 + SNIPPET_RADIUS);
+        let mut snippet = String::new();
+        if start > 0 {
+            snippet.push_str("...");
+        }
+        snippet.extend(&chars[start..end]);
+        if end < chars.len() {
+            snippet.push_str("...");
+        }
+        let caret_column = column - 1 - start + if start > 0 { 3 } else { 0 };
+        snippet.push('\n');
+        snippet.push_str(&" ".repeat(caret_column));
+        snippet.push('^');
+
+        Self {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column,
+            expected,
+            found: input[offset..].chars().next(),
+            snippet,
+            io: None,
+        }
+    }
+
+    pub(crate) fn io(error: &std::io::Error, offset: usize, line: usize) -> Self {
+        Self {
+            offset,
+            line,
+            column: 1,
+            expected: None,
+            found: None,
+            snippet: String::new(),
+            io: Some((error.kind(), error.to_string())),
+        }
+    }
+
+    /// move an error computed on a window of the document to its place in the whole document,
+    /// `offset`, `line` and `column` are where the window start
+    pub(crate) fn shift(mut self, offset: usize, line: usize, column: usize) -> Self {
+        if self.line == 1 {
+            self.column += column - 1;
+        }
+        self.line += line - 1;
+        self.offset += offset;
+        self
+    }
+
+    /// byte offset into the document
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// 1-based line number
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// 1-based column counted in chars
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// e.g. "`,` or `}`", `None` if the grammar can't tell
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    /// the offending line with a `^` under the error column
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+
+    /// `Some` if reading the input failed rather than parsing it
+    pub fn io_error_kind(&self) -> Option<std::io::ErrorKind> {
+        self.io.as_ref().map(|(kind, _)| *kind)
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((_, message)) = &self.io {
+            return write!(f, "I/O error: {} at line {}", message, self.line);
+        }
+        match &self.expected {
+            Some(expected) => write!(f, "expected {}", expected)?,
+            None => f.write_str("invalid JSON")?,
+        }
+        match self.found {
+            Some(c) if c.is_control() => write!(f, ", found `{}`", c.escape_debug())?,
+            Some(c) => write!(f, ", found `{}`", c)?,
+            None => f.write_str(", found end of input")?,
+        }
+        write!(
+            f,
+            " at line {} column {}\n{}",
+            self.line, self.column, self.snippet
+        )
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+#[test]
+fn test_json_error() {
+    use crate::JsonValue;
+
+    let e = JsonValue::from_str("{\n  \"a\": 1\n  \"b\": 2\n}").unwrap_err();
+    assert_eq!((e.offset(), e.line(), e.column()), (13, 3, 3));
+    assert_eq!(e.expected(), Some("`,` or `}`"));
+    assert_eq!(e.snippet(), "  \"b\": 2\n  ^");
+    assert_eq!(
+        e.to_string(),
+        "expected `,` or `}`, found `\"` at line 3 column 3\n  \"b\": 2\n  ^"
+    );
+
+    let e = JsonValue::from_str("[1, ]").unwrap_err();
+    assert_eq!((e.column(), e.expected()), (5, Some("a JSON value")));
+    let e = JsonValue::from_str(r#"{"a" 1}"#).unwrap_err();
+    assert_eq!((e.column(), e.expected()), (6, Some("`:`")));
+    let e = JsonValue::from_str(r#"{1: 2}"#).unwrap_err();
+    assert_eq!((e.column(), e.expected()), (2, Some("a string key")));
+    let e = JsonValue::from_str(r#"["abc"#).unwrap_err();
+    assert_eq!((e.column(), e.expected()), (6, Some("`\"`")));
+    assert!(e.to_string().contains("found end of input"));
+    let e = JsonValue::from_str(r#"["\x"]"#).unwrap_err();
+    assert_eq!((e.column(), e.expected()), (4, Some("an escape sequence")));
+    let e = JsonValue::from_str("[1e400]").unwrap_err();
+    assert_eq!(
+        (e.column(), e.expected()),
+        (2, Some("a number in f64 range"))
+    );
+
+    let long_line = format!("[{}1 2]", "1, ".repeat(100));
+    let e = JsonValue::from_str(&long_line).unwrap_err();
+    assert_eq!(e.column(), 304);
+    assert!(
+        e.snippet().starts_with("...")
+            && e.snippet()
+                .ends_with("2]\n                                           ^")
+    );
+}
@@ -0,0 +1,228 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_parser::{
+    build_json_document, duplicate_key, DuplicateKeyPolicy, JsonBuilder, ParseOptions, Trivia,
+};
+use crate::JsonValue;
+use nom::{
+    combinator::eof,
+    error::{context, ContextError, ParseError},
+    sequence::terminated,
+    IResult,
+};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// one step of a depth-first walk over a JSON document
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent<'a> {
+    StartObject,
+    /// an object key, the next event is the start of its value
+    Key(Cow<'a, str>),
+    EndObject,
+    StartArray,
+    EndArray,
+    /// null, boolean, number or string
+    Value(JsonValue<'a>),
+}
+
+/// sends the events of the grammar to `f`
+struct EventBuilder<'f, F> {
+    f: &'f mut F,
+    policy: DuplicateKeyPolicy,
+}
+
+impl<'a, E, F> JsonBuilder<'a, E> for EventBuilder<'_, F>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    F: FnMut(JsonEvent<'a>),
+{
+    type Value = ();
+    type Array = ();
+    /// keys are only remembered to reject duplicates, the other policies pick among the values
+    /// of the whole object which events can't do, so every key is reported as written
+    type Map = HashSet<Cow<'a, str>>;
+    type Key = ();
+
+    fn scalar(&mut self, _start: &'a str, value: JsonValue<'a>, _rest: &'a str) {
+        (self.f)(JsonEvent::Value(value));
+    }
+
+    fn start_array(&mut self, _start: &'a str) {
+        (self.f)(JsonEvent::StartArray);
+    }
+
+    fn item(&mut self, _array: &mut (), _value: (), _trivia: Trivia<'a>) {}
+
+    fn end_array(&mut self, _array: (), _close: &'a str, _rest: &'a str) {
+        (self.f)(JsonEvent::EndArray);
+    }
+
+    fn start_map(&mut self, _start: &'a str) -> Self::Map {
+        (self.f)(JsonEvent::StartObject);
+        HashSet::new()
+    }
+
+    fn key(
+        &mut self,
+        keys: &mut Self::Map,
+        start: &'a str,
+        key: Cow<'a, str>,
+        _rest: &'a str,
+    ) -> Result<(), nom::Err<E>> {
+        if self.policy == DuplicateKeyPolicy::Error && !keys.insert(key.clone()) {
+            return Err(duplicate_key(start));
+        }
+        (self.f)(JsonEvent::Key(key));
+        Ok(())
+    }
+
+    fn member(&mut self, _keys: &mut Self::Map, _key: (), _value: (), _trivia: Trivia<'a>) {}
+
+    fn end_map(
+        &mut self,
+        _keys: Self::Map,
+        _close: &'a str,
+        _rest: &'a str,
+    ) -> Result<(), nom::Err<E>> {
+        (self.f)(JsonEvent::EndObject);
+        Ok(())
+    }
+}
+
+/// event version of [`crate::json_parser::parse_json_str_with`], it accepts the same documents
+/// and fails at the same place with the same error
+pub fn parse_json_events<'a, E, F>(
+    i: &'a str,
+    options: ParseOptions,
+    f: &mut F,
+) -> IResult<&'a str, (), E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    F: FnMut(JsonEvent<'a>),
+{
+    let mut builder = EventBuilder {
+        f,
+        policy: options.duplicate_key_policy,
+    };
+    let (i, _) = build_json_document(i, options, &mut builder)?;
+    Ok((i, ()))
+}
+
+/// call `f` for every event of the document without building a [`JsonValue`] tree, memory use
+/// only grows with the nesting depth. On a syntax error the events before it were already sent
+pub fn parse_events<'a>(input: &'a str, f: impl FnMut(JsonEvent<'a>)) -> Result<(), JsonError> {
+    parse_events_with(input, ParseOptions::default(), f)
+}
+
+pub fn parse_events_with<'a>(
+    input: &'a str,
+    options: ParseOptions,
+    mut f: impl FnMut(JsonEvent<'a>),
+) -> Result<(), JsonError> {
+    terminated(
+        |i| parse_json_events::<JsonParseError, _>(i, options, &mut f),
+        context("end of input", eof),
+    )(input)
+    .map(|_| ())
+    .map_err(|e| JsonError::from_nom(input, e))
+}
+
+#[test]
+fn test_json_events() {
+    use crate::json_parser::{Dialect, RootMode};
+
+    let text = r#"{"users": [{"id": 1, "name": "a"}, {"id": 2, "tags": []}], "total": 2}"#;
+    let mut events = Vec::new();
+    parse_events(text, |event| events.push(event)).unwrap();
+    assert_eq!(events.len(), 20);
+    assert_eq!(
+        events[..4],
+        [
+            JsonEvent::StartObject,
+            JsonEvent::Key("users".into()),
+            JsonEvent::StartArray,
+            JsonEvent::StartObject,
+        ]
+    );
+    assert_eq!(events.last(), Some(&JsonEvent::EndObject));
+
+    // pick the ids out of the document without keeping anything else
+    let mut depth = 0;
+    let mut in_id = false;
+    let mut ids = Vec::new();
+    parse_events(text, |event| match event {
+        JsonEvent::StartObject | JsonEvent::StartArray => depth += 1,
+        JsonEvent::EndObject | JsonEvent::EndArray => depth -= 1,
+        JsonEvent::Key(key) => in_id = depth == 3 && key == "id",
+        JsonEvent::Value(value) if in_id => ids.push(value),
+        JsonEvent::Value(_) => {}
+    })
+    .unwrap();
+    assert_eq!(ids, [JsonValue::NumberU64(1), JsonValue::NumberU64(2)]);
+
+    // the errors are the same as the tree parser ones
+    for text in [
+        "[1, ]",
+        r#"{"a" 1}"#,
+        r#"{"a": 1,}"#,
+        "[1 2]",
+        r#"{"a": 1 "b": 2}"#,
+        "[1e400]",
+        "[] []",
+        "",
+    ] {
+        let error = parse_events(text, |_| {}).unwrap_err();
+        assert_eq!(Err(error), JsonValue::from_str(text), "{}", text);
+    }
+    for duplicate_key_policy in [DuplicateKeyPolicy::Error, DuplicateKeyPolicy::KeepAll] {
+        let options = ParseOptions {
+            duplicate_key_policy,
+            root_mode: RootMode::Rfc4627,
+            ..ParseOptions::default()
+        };
+        for text in [r#"{"a": 1, "a": 2}"#, "1"] {
+            let result = parse_events_with(text, options, |_| {});
+            assert_eq!(result, JsonValue::from_str_with(text, options).map(|_| ()));
+        }
+    }
+
+    // the JSON5 and JSONC syntax is the one of the tree parser too
+    for (dialect, text) in [
+        (
+            Dialect::Json5,
+            "{a: [+1, 'x', Infinity,], /* c */ \"b\": {},}",
+        ),
+        (Dialect::Json5, "// c\n[.5, 0x1F]"),
+        (Dialect::Json5, "[1,,]"),
+        (Dialect::Json5, "{a: 1,,}"),
+        (Dialect::Json5, "{,}"),
+        (Dialect::Jsonc, "{\"a\": [1, /* c */ 2,], // c\n}"),
+        (Dialect::Jsonc, "{\"a\" /* c */ : // c\n 1}"),
+        (Dialect::Jsonc, "{a: 1}"),
+        (Dialect::Jsonc, "[1 /* c"),
+    ] {
+        let options = ParseOptions {
+            dialect,
+            ..ParseOptions::default()
+        };
+        let result = parse_events_with(text, options, |_| {});
+        assert_eq!(
+            result,
+            JsonValue::from_str_with(text, options).map(|_| ()),
+            "{}",
+            text
+        );
+    }
+    let mut keys = Vec::new();
+    let json5 = ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    };
+    parse_events_with("{a: 1, 'b': 2, \"c\": 3}", json5, |event| {
+        if let JsonEvent::Key(key) = event {
+            keys.push(key);
+        }
+    })
+    .unwrap();
+    assert_eq!(keys, ["a", "b", "c"]);
+}
@@ -0,0 +1,146 @@
+use crate::json_error::JsonError;
+use crate::json_parser::ParseOptions;
+use crate::JsonValue;
+use std::io::BufRead;
+
+/// Iterator over newline-delimited JSON (NDJSON / JSON Lines), one value per line.
+///
+/// Blank lines are skipped and `\r\n` line endings are accepted. Error positions count lines and
+/// bytes from the start of the whole stream. Iteration ends after the first error unless
+/// [`JsonLinesReader::continue_on_error`] is set, an I/O error always ends it
+pub struct JsonLinesReader<R> {
+    reader: R,
+    options: ParseOptions,
+    continue_on_error: bool,
+    buf: Vec<u8>,
+    /// line number and byte offset of the next line to read
+    line: usize,
+    offset: usize,
+    done: bool,
+}
+
+impl<R: BufRead> JsonLinesReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Self {
+            reader,
+            options,
+            continue_on_error: false,
+            buf: Vec::new(),
+            line: 1,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// keep reading after a line that isn't valid JSON, the error is still returned for it
+    pub fn continue_on_error(mut self, enabled: bool) -> Self {
+        self.continue_on_error = enabled;
+        self
+    }
+
+    /// parse the content of the line starting at `offset`, without its line ending
+    fn parse_line(&self, line: usize, offset: usize) -> Result<JsonValue<'static>, JsonError> {
+        let bytes = self.buf.strip_suffix(b"\n").unwrap_or(&self.buf);
+        let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+        let text = std::str::from_utf8(bytes).map_err(|e| {
+            let valid =
+                std::str::from_utf8(&bytes[..e.valid_up_to()]).expect("checked by valid_up_to");
+            JsonError::at(valid, valid.len(), Some("valid UTF-8".to_string()))
+        });
+        text.and_then(|text| {
+            JsonValue::from_str_with(text, self.options).map(JsonValue::into_owned)
+        })
+        .map_err(|e| e.shift(offset, line, 1))
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLinesReader<R> {
+    type Item = Result<JsonValue<'static>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buf.clear();
+            let (line, offset) = (self.line, self.offset);
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => self.done = true,
+                Ok(len) => {
+                    self.line += 1;
+                    self.offset += len;
+                    // only JSON whitespace, a form feed is a syntax error like anywhere else
+                    if self
+                        .buf
+                        .iter()
+                        .all(|b| matches!(b, b' ' | b'\t' | b'\r' | b'\n'))
+                    {
+                        continue;
+                    }
+                    let result = self.parse_line(line, offset);
+                    self.done = result.is_err() && !self.continue_on_error;
+                    return Some(result);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(JsonError::io(&e, offset, line)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_json_lines() {
+    let text: &[u8] = b"{\"a\": 1}\r\n\n  \n[2, 3]\n{\"b\": }\n\"\xff\"\n4";
+    let results = JsonLinesReader::new(text)
+        .continue_on_error(true)
+        .map(|r| {
+            r.map(|v| v.to_string())
+                .map_err(|e| (e.line(), e.column(), e.offset()))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        [
+            Ok(r#"{"a":1}"#.to_string()),
+            Ok("[2,3]".to_string()),
+            Err((5, 7, 27)),
+            Err((6, 2, 30)),
+            Ok("4".to_string()),
+        ]
+    );
+    let e = JsonLinesReader::new(text).find_map(Result::err).unwrap();
+    assert_eq!(
+        e.to_string(),
+        "expected a JSON value, found `}` at line 5 column 7\n{\"b\": }\n      ^"
+    );
+    assert_eq!(JsonLinesReader::new(text).count(), 3);
+
+    struct Broken;
+    impl std::io::Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+    let mut reader = JsonLinesReader::new(std::io::BufReader::new(Broken));
+    let e = reader.next().unwrap().unwrap_err();
+    assert_eq!(e.io_error_kind(), Some(std::io::ErrorKind::BrokenPipe));
+    assert!(reader.next().is_none());
+
+    let results = JsonLinesReader::new(&b"1\n\x0C\n2"[..])
+        .continue_on_error(true)
+        .map(|r| r.map_err(|e| e.line()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        results,
+        [
+            Ok(JsonValue::NumberU64(1)),
+            Err(2),
+            Ok(JsonValue::NumberU64(2))
+        ]
+    );
+}
@@ -0,0 +1,272 @@
+use crate::json_parser::DuplicateKeyPolicy;
+use crate::JsonValue;
+use std::borrow::Cow;
+use std::collections::{hash_map::Entry, HashMap};
+use std::fmt;
+
+/// the duplicate key resolution of [`JsonMap`] for any entry type, shared with the spanned tree
+pub(crate) fn dedupe_entries<K: AsRef<str>, V>(
+    entries: Vec<(K, V)>,
+    policy: DuplicateKeyPolicy,
+) -> Result<Vec<(K, V)>, usize> {
+    if policy == DuplicateKeyPolicy::KeepAll {
+        return Ok(entries);
+    }
+    // which output slot already hold the key of each entry, None if the key is new
+    let mut duplicate_of = Vec::with_capacity(entries.len());
+    let mut slots = HashMap::<&str, usize>::with_capacity(entries.len());
+    for (i, (key, _)) in entries.iter().enumerate() {
+        let next_slot = slots.len();
+        match slots.entry(key.as_ref()) {
+            Entry::Vacant(entry) => {
+                entry.insert(next_slot);
+                duplicate_of.push(None);
+            }
+            Entry::Occupied(_) if policy == DuplicateKeyPolicy::Error => return Err(i),
+            Entry::Occupied(entry) => duplicate_of.push(Some(*entry.get())),
+        }
+    }
+    let mut deduped = Vec::<(K, V)>::with_capacity(slots.len());
+    for (entry, duplicate_of) in entries.into_iter().zip(duplicate_of) {
+        match duplicate_of {
+            None => deduped.push(entry),
+            Some(slot) if policy == DuplicateKeyPolicy::LastWins => deduped[slot].1 = entry.1,
+            Some(_) => {}
+        }
+    }
+    Ok(deduped)
+}
+
+/// JavaScript Object that remembers the insertion order of its keys.
+///
+/// Entries live in a Vec so [`DuplicateKeyPolicy::KeepAll`] can keep repeated keys, a hash
+/// index next to it makes `get` and `insert` O(1) while `remove` is O(n) like `Vec::remove`.
+///
+/// The order is kept for iteration and printing only: `==` ignores it, as it did when objects
+/// were a `HashMap`, so `{"a": 1, "b": 2} == {"b": 2, "a": 1}`. Compare [`JsonMap::iter`] to
+/// also check the order
+#[derive(Clone, Default)]
+pub struct JsonMap<'a> {
+    entries: Vec<(Cow<'a, str>, JsonValue<'a>)>,
+    /// position of the first entry of each key
+    index: HashMap<Cow<'a, str>, usize>,
+}
+
+impl fmt::Debug for JsonMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonMap")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl<'a> JsonMap<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// resolve duplicate keys in `entries` according to `policy`,
+    /// `Err` is the index of the first duplicate entry when the policy is Error
+    pub(crate) fn from_entries_with(
+        entries: Vec<(Cow<'a, str>, JsonValue<'a>)>,
+        policy: DuplicateKeyPolicy,
+    ) -> Result<Self, usize> {
+        dedupe_entries(entries, policy).map(Self::from_entries)
+    }
+
+    fn from_entries(entries: Vec<(Cow<'a, str>, JsonValue<'a>)>) -> Self {
+        let mut index = HashMap::with_capacity(entries.len());
+        for (pos, (key, _)) in entries.iter().enumerate() {
+            index.entry(key.clone()).or_insert(pos);
+        }
+        Self { entries, index }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// value of the first entry with `key`
+    pub fn get(&self, key: &str) -> Option<&JsonValue<'a>> {
+        self.index.get(key).map(|&pos| &self.entries[pos].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue<'a>> {
+        self.index.get(key).map(|&pos| &mut self.entries[pos].1)
+    }
+
+    /// every value stored under `key`, more than one only with [`DuplicateKeyPolicy::KeepAll`]
+    pub fn get_all<'s>(&'s self, key: &'s str) -> impl Iterator<Item = &'s JsonValue<'a>> + 's {
+        self.entries
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// replace the value of an existing key in place, otherwise append the key at the end
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: JsonValue<'a>,
+    ) -> Option<JsonValue<'a>> {
+        let key = key.into();
+        match self.get_mut(&key) {
+            Some(old) => Some(std::mem::replace(old, value)),
+            None => {
+                self.push(key, value);
+                None
+            }
+        }
+    }
+
+    /// append an entry even if the key already exists
+    pub fn push(&mut self, key: impl Into<Cow<'a, str>>, value: JsonValue<'a>) {
+        let key = key.into();
+        self.index.entry(key.clone()).or_insert(self.entries.len());
+        self.entries.push((key, value));
+    }
+
+    /// remove every entry with `key` and return the first removed value, keep the order of the rest
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue<'a>> {
+        let pos = *self.index.get(key)?;
+        let (_, value) = self.entries.remove(pos);
+        self.entries.retain(|(k, _)| k != key);
+        *self = Self::from_entries(std::mem::take(&mut self.entries));
+        Some(value)
+    }
+
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&str, &JsonValue<'a>)> + ExactSizeIterator {
+        self.entries.iter().map(|(k, v)| (k.as_ref(), v))
+    }
+
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = (&str, &mut JsonValue<'a>)> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(k, v)| (&**k, v))
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.entries.iter().map(|(k, _)| k.as_ref())
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &JsonValue<'a>> + ExactSizeIterator {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn into_owned(self) -> JsonMap<'static> {
+        JsonMap::from_entries(
+            self.entries
+                .into_iter()
+                .map(|(k, v)| (Cow::Owned(k.into_owned()), v.into_owned()))
+                .collect(),
+        )
+    }
+}
+
+/// key order doesn't matter for equality, like two JavaScript objects with the same properties,
+/// but repeated keys must repeat in the same relative order
+impl PartialEq for JsonMap<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        fn sorted<'m, 'a>(map: &'m JsonMap<'a>) -> Vec<&'m (Cow<'a, str>, JsonValue<'a>)> {
+            let mut entries = map.entries.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            entries
+        }
+        sorted(self) == sorted(other)
+    }
+}
+
+/// later entries overwrite earlier ones with the same key like [`JsonMap::insert`]
+impl<'a, K: Into<Cow<'a, str>>> FromIterator<(K, JsonValue<'a>)> for JsonMap<'a> {
+    fn from_iter<T: IntoIterator<Item = (K, JsonValue<'a>)>>(iter: T) -> Self {
+        let entries = iter.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        Self::from_entries_with(entries, DuplicateKeyPolicy::LastWins)
+            .expect("LastWins never reject duplicate")
+    }
+}
+
+impl<'a> IntoIterator for JsonMap<'a> {
+    type Item = (Cow<'a, str>, JsonValue<'a>);
+    type IntoIter = std::vec::IntoIter<(Cow<'a, str>, JsonValue<'a>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[test]
+fn test_json_map() {
+    use crate::json_parser::ParseOptions;
+
+    let text = r#"{"b": 1, "a": 2, "b": 3, "c": 4}"#;
+    let parse = |duplicate_key_policy| {
+        let options = ParseOptions {
+            duplicate_key_policy,
+            ..ParseOptions::default()
+        };
+        match JsonValue::from_str_with(text, options) {
+            Ok(JsonValue::Map(map)) => Ok(map
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()),
+            Ok(_) => unreachable!(),
+            Err(e) => Err(e),
+        }
+    };
+    assert_eq!(
+        parse(DuplicateKeyPolicy::LastWins).unwrap(),
+        ["b=3", "a=2", "c=4"]
+    );
+    assert_eq!(
+        parse(DuplicateKeyPolicy::FirstWins).unwrap(),
+        ["b=1", "a=2", "c=4"]
+    );
+    assert_eq!(
+        parse(DuplicateKeyPolicy::KeepAll).unwrap(),
+        ["b=1", "a=2", "b=3", "c=4"]
+    );
+    let e = parse(DuplicateKeyPolicy::Error).unwrap_err();
+    assert_eq!((e.column(), e.expected()), (18, Some("a unique key")));
+
+    let value = JsonValue::from_str(text).unwrap();
+    assert_eq!(value.to_string(), r#"{"b":3,"a":2,"c":4}"#);
+    let reordered = JsonValue::from_str(r#"{"c": 4, "b": 3, "a": 2}"#).unwrap();
+    assert_eq!(value, reordered);
+    assert_ne!(value.to_string(), reordered.to_string());
+
+    let mut map = JsonMap::new();
+    assert_eq!(map.insert("z", JsonValue::Null), None);
+    map.push("y", JsonValue::Boolean(true));
+    map.push("z", JsonValue::Boolean(false));
+    assert_eq!(map.get_all("z").count(), 2);
+    assert_eq!(
+        map.insert("z", JsonValue::NumberU64(1)),
+        Some(JsonValue::Null)
+    );
+    assert_eq!(map.get("z"), Some(&JsonValue::NumberU64(1)));
+    assert_eq!(map.remove("z"), Some(JsonValue::NumberU64(1)));
+    assert_eq!(map.keys().collect::<Vec<_>>(), ["y"]);
+
+    // lookups go through the index, also after a removal shifted the entries
+    let mut map = (0..100_000)
+        .map(|i| (i.to_string(), JsonValue::from(i)))
+        .collect::<JsonMap>();
+    assert!((0..100_000).all(|i| map.get(&i.to_string()) == Some(&JsonValue::from(i))));
+    assert_eq!(map.remove("0"), Some(JsonValue::from(0)));
+    assert_eq!(map.get("1"), Some(&JsonValue::from(1)));
+    assert_eq!(map.insert("0", JsonValue::Null), None);
+    assert_eq!(map.keys().next_back(), Some("0"));
+}
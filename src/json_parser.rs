@@ -1,123 +1,885 @@
+use crate::json5::{parse_json5_key, parse_json5_number_with, parse_json5_string, split_json5};
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_map::JsonMap;
+use crate::raw_number::RawNumber;
+use std::borrow::Cow;
+use std::str::FromStr;
+
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while},
-    character::complete::{alphanumeric1, char as char_},
-    combinator::{map, value},
-    error::{ErrorKind, ParseError},
-    multi::separated_list0,
-    number::complete::double,
-    sequence::{delimited, preceded, separated_pair, terminated},
+    bytes::complete::{tag, take_until, take_while, take_while1, take_while_m_n},
+    character::complete::{char as char_, digit0, digit1, one_of, satisfy},
+    combinator::{cut, eof, map, map_opt, opt, recognize, value},
+    error::{context, ContextError, ErrorKind, ParseError},
+    multi::{fold_many0, many0_count},
+    sequence::{pair, preceded, terminated, tuple},
     IResult,
 };
-use std::collections::HashMap;
 
-#[derive(Debug)]
-enum JsonValue {
+/// A parsed JSON tree borrowing from the input text, strings without escapes are slices of the
+/// input so parsing doesn't allocate per field. Use [`JsonValue::into_owned`] to detach it
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue<'a> {
     Null,
 
     /// JavaScript primitive types is bool,f64,String
     Boolean(bool),
+    /// integer literal such as a large ID keep its exact value instead of rounding to f64
+    NumberI64(i64),
+    NumberU64(u64),
     NumberF64(f64),
-    /// JSON only allow double quote String expression, JavaScript can use single quote String expression
-    String(String),
+    /// only produced in [`NumberMode::ArbitraryPrecision`], e.g. 128-bit IDs or monetary decimals
+    Number(RawNumber),
+    /// JSON only allow double quote String expression, JavaScript and [`Dialect::Json5`] can use
+    /// single quote String expression
+    String(Cow<'a, str>),
 
-    Array(Vec<JsonValue>),
-    /// JavaScript Object
-    Map(HashMap<String, JsonValue>),
+    Array(Vec<JsonValue<'a>>),
+    /// JavaScript Object, keys keep the order they appear in the document
+    Map(JsonMap<'a>),
 }
 
-impl JsonValue {
-    fn from_str(s: &str) -> Result<Self, String> {
-        if !s.chars().all(|c| c.is_ascii()) {
-            return Err(
-                "only support ASCII alphanumeric, does not support string contains Unicode"
-                    .to_string(),
-            );
-        }
-        match parse_json_str::<(&str, ErrorKind)>(s) {
+/// how number literals are stored in the parsed tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberMode {
+    /// integer as NumberU64/NumberI64 if it fits, otherwise NumberF64
+    #[default]
+    Native,
+    /// keep the original literal text as [`JsonValue::Number`] so nothing get rounded
+    ArbitraryPrecision,
+}
+
+/// which values may appear at the top level of a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RootMode {
+    /// RFC 8259 allow any value such as `"abc"` or `42` as the root
+    #[default]
+    AnyValue,
+    /// legacy RFC 4627 only allow an object or an array as the root
+    Rfc4627,
+}
+
+/// what to do when an object has the same key more than once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeyPolicy {
+    /// reject the document with an error pointing at the repeated key
+    Error,
+    /// keep the value of the first occurrence
+    FirstWins,
+    /// keep the value of the last occurrence at the position of the first one, like `JSON.parse`
+    #[default]
+    LastWins,
+    /// keep every entry, see [`JsonMap::get_all`]
+    KeepAll,
+}
+
+/// the syntax of the document, read by the tree parser of this module and [`crate::json_spanned`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// RFC 8259
+    #[default]
+    Json,
+    /// [JSON5](https://spec.json5.org): comments, trailing commas, identifier keys, single quoted
+    /// and multi-line strings, hex numbers, `Infinity`, `NaN`, `+1`, `.5` and `5.`
+    Json5,
+    /// JSON with comments as in VS Code settings: JSON plus `//` and `/* */` comments and
+    /// trailing commas
+    Jsonc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    pub number_mode: NumberMode,
+    pub root_mode: RootMode,
+    pub duplicate_key_policy: DuplicateKeyPolicy,
+    pub dialect: Dialect,
+}
+
+impl<'a> JsonValue<'a> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'a str) -> Result<Self, JsonError> {
+        Self::from_str_with(s, ParseOptions::default())
+    }
+
+    /// the whole input must be one JSON document, only whitespace may follow the root value
+    pub fn from_str_with(s: &'a str, options: ParseOptions) -> Result<Self, JsonError> {
+        match terminated(
+            parse_json_str_with::<JsonParseError>(options),
+            context("end of input", eof),
+        )(s)
+        {
             Ok(val) => Ok(val.1),
-            Err(e) => Err(e.to_string()),
+            Err(e) => Err(JsonError::from_nom(s, e)),
         }
     }
+
+    /// parse one document from the start of `s` and return the unconsumed tail,
+    /// e.g. to read concatenated documents one after another
+    pub fn parse_prefix(s: &'a str) -> Result<(Self, &'a str), JsonError> {
+        Self::parse_prefix_with(s, ParseOptions::default())
+    }
+
+    pub fn parse_prefix_with(
+        s: &'a str,
+        options: ParseOptions,
+    ) -> Result<(Self, &'a str), JsonError> {
+        match parse_json_str_with::<JsonParseError>(options)(s) {
+            Ok((rest, val)) => Ok((val, rest)),
+            Err(e) => Err(JsonError::from_nom(s, e)),
+        }
+    }
+
+    /// copy every borrowed string so the value no longer depends on the input text
+    pub fn into_owned(self) -> JsonValue<'static> {
+        match self {
+            JsonValue::Null => JsonValue::Null,
+            JsonValue::Boolean(b) => JsonValue::Boolean(b),
+            JsonValue::NumberI64(n) => JsonValue::NumberI64(n),
+            JsonValue::NumberU64(n) => JsonValue::NumberU64(n),
+            JsonValue::NumberF64(n) => JsonValue::NumberF64(n),
+            JsonValue::Number(n) => JsonValue::Number(n),
+            JsonValue::String(s) => JsonValue::String(Cow::Owned(s.into_owned())),
+            JsonValue::Array(array) => {
+                JsonValue::Array(array.into_iter().map(JsonValue::into_owned).collect())
+            }
+            JsonValue::Map(map) => JsonValue::Map(map.into_owned()),
+        }
+    }
+}
+
+/// `"...".parse::<JsonValue>()` for callers that want an owned tree right away
+impl FromStr for JsonValue<'static> {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JsonValue::from_str(s).map(JsonValue::into_owned)
+    }
 }
 
 /// split whitespace or tab or newline
-fn split<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+pub fn split<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     take_while(|c| " \t\r\n".contains(c))(i)
 }
 
-/// match a pair of double quote
-fn parse_string<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
-    preceded(char_('\"'), terminated(alphanumeric1, char_('\"')))(i)
+/// `// ...` up to the end of the line or `/* ... */`
+pub fn parse_comment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    alt((
+        recognize(pair(
+            tag("//"),
+            take_while(|c| !matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')),
+        )),
+        recognize(pair(
+            tag("/*"),
+            cut(context("`*/`", terminated(take_until("*/"), tag("*/")))),
+        )),
+    ))(i)
 }
 
-fn parse_json_map<'a, E: ParseError<&'a str>>(
+/// [`split`] that also skips comments
+pub fn split_jsonc<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     i: &'a str,
-) -> IResult<&'a str, HashMap<String, JsonValue>, E> {
-    preceded(
-        char_('{'),
-        terminated(
-            map(
-                separated_list0(
-                    preceded(split, char_(',')),
-                    separated_pair(
-                        preceded(split, parse_string),
-                        preceded(split, char_(':')),
-                        parse_json_value,
-                    ),
-                ),
-                |tuple_vec| {
-                    tuple_vec
-                        .into_iter()
-                        .map(|(k, v)| (String::from(k), v))
-                        .collect()
-                },
-            ),
-            preceded(split, char_('}')),
-        ),
+) -> IResult<&'a str, &'a str, E> {
+    recognize(many0_count(alt((
+        take_while1(|c| " \t\r\n".contains(c)),
+        parse_comment,
+    ))))(i)
+}
+
+/// the whitespace and comments of [`split`] in each dialect
+pub fn split_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    dialect: Dialect,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, E> + Copy {
+    move |i| match dialect {
+        Dialect::Json => split(i),
+        Dialect::Json5 => split_json5(i),
+        Dialect::Jsonc => split_jsonc(i),
+    }
+}
+
+/// RFC 8259 forbids raw control characters in strings
+fn is_literal_char(c: char, quote: char) -> bool {
+    c != quote && c != '\\' && c >= '\u{20}'
+}
+
+/// four hex digits of a `\uXXXX` escape
+fn parse_hex4<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, u16, E> {
+    map_opt(
+        take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+        |hex| u16::from_str_radix(hex, 16).ok(),
     )(i)
 }
 
-fn parse_json_array<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, Vec<JsonValue>, E> {
+/// characters outside the BMP are escaped as a UTF-16 surrogate pair like `\uD83D\uDE00`,
+/// a lone surrogate can't be represented in a Rust String so it is rejected
+pub(crate) fn parse_unicode_escape<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, char, E> {
+    let (rest, high) = preceded(char_('u'), context("4 hex digits", parse_hex4))(i)?;
+    match high {
+        0xD800..=0xDBFF => context(
+            "a low surrogate escape",
+            map_opt(preceded(tag("\\u"), parse_hex4), |low| {
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return None;
+                }
+                let high = u32::from(high) - 0xD800;
+                let low = u32::from(low) - 0xDC00;
+                char::from_u32(0x10000 + (high << 10) + low)
+            }),
+        )(rest),
+        _ => match char::from_u32(u32::from(high)) {
+            Some(c) => Ok((rest, c)),
+            None => Err(nom::Err::Error(E::add_context(
+                i,
+                "a high surrogate before the low surrogate",
+                E::from_error_kind(i, ErrorKind::Char),
+            ))),
+        },
+    }
+}
+
+/// JSON only allow escape `\" \\ \/ \b \f \n \r \t \uXXXX`, `quote` is the escaped quote
+fn parse_escaped_char<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    quote: char,
+) -> impl FnMut(&'a str) -> IResult<&'a str, char, E> {
     preceded(
-        char_('['),
-        terminated(
-            separated_list0(preceded(split, char_(',')), parse_json_value),
-            preceded(split, char_(']')),
-        ),
-    )(i)
+        char_('\\'),
+        cut(context(
+            "an escape sequence",
+            alt((
+                value(quote, char_(quote)),
+                value('\\', char_('\\')),
+                value('/', char_('/')),
+                value('\u{08}', char_('b')),
+                value('\u{0C}', char_('f')),
+                value('\n', char_('n')),
+                value('\r', char_('r')),
+                value('\t', char_('t')),
+                parse_unicode_escape,
+            )),
+        )),
+    )
 }
 
-/// The root node of json tree must be one of Null/Array/Map
-fn parse_json_root<'a, E: ParseError<&'a str>>(
-    _i: &str,
-) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue, E> {
+enum StringFragment<'a> {
+    Literal(&'a str),
+    EscapedChar(char),
+}
+
+/// match a pair of double quote and unescape the content between them,
+/// a string without escape is borrowed from the input
+pub fn parse_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    parse_quoted_string('"')(i)
+}
+
+/// [`parse_string`] with another quote char, e.g. the single quoted strings of JSONPath
+pub(crate) fn parse_quoted_string<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    quote: char,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Cow<'a, str>, E> {
+    move |i| {
+        let (rest, literal) = preceded(char_(quote), take_while(|c| is_literal_char(c, quote)))(i)?;
+        if let Ok((rest, _)) = char_::<_, E>(quote)(rest) {
+            return Ok((rest, Cow::Borrowed(literal)));
+        }
+        map(
+            terminated(
+                fold_many0(
+                    alt((
+                        map(
+                            take_while1(|c| is_literal_char(c, quote)),
+                            StringFragment::Literal,
+                        ),
+                        map(parse_escaped_char(quote), StringFragment::EscapedChar),
+                    )),
+                    move || literal.to_string(),
+                    |mut string, fragment| {
+                        match fragment {
+                            StringFragment::Literal(s) => string.push_str(s),
+                            StringFragment::EscapedChar(c) => string.push(c),
+                        }
+                        string
+                    },
+                ),
+                char_(quote),
+            ),
+            Cow::Owned,
+        )(rest)
+    }
+}
+
+/// `0` or a digit sequence without leading zero
+pub(crate) fn parse_integer_part<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
     alt((
-        map(parse_json_map, JsonValue::Map),
-        map(parse_json_array, JsonValue::Array),
-        map(|i| value((), tag("null"))(i), |_| JsonValue::Null),
+        tag("0"),
+        recognize(pair(satisfy(|c| ('1'..='9').contains(&c)), digit0)),
+    ))(i)
+}
+
+/// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`, unlike nom's `double` JSON forbids
+/// `+1`, `.5`, `1.`, leading zeros, `inf` and `NaN`
+pub fn parse_number_literal<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    recognize(tuple((
+        alt((
+            preceded(char_('-'), cut(context("a digit", parse_integer_part))),
+            parse_integer_part,
+        )),
+        opt(preceded(char_('.'), cut(context("a digit", digit1)))),
+        opt(tuple((
+            one_of("eE"),
+            opt(one_of("+-")),
+            cut(context("a digit", digit1)),
+        ))),
+    )))(i)
+}
+
+/// convert a literal matched by [`parse_number_literal`], integer literal keep its exact value
+/// as u64 or i64 and fallback to f64 if it overflow. `None` if it is out of f64 range
+pub(crate) fn number_from_literal(literal: &str, mode: NumberMode) -> Option<JsonValue<'static>> {
+    if mode == NumberMode::ArbitraryPrecision {
+        return Some(JsonValue::Number(RawNumber(literal.to_string())));
+    }
+    // an integer can't keep the sign of `-0`
+    if literal == "-0" {
+        return Some(JsonValue::NumberF64(-0.0));
+    }
+    if !literal.contains(['.', 'e', 'E']) {
+        if let Ok(n) = literal.parse::<u64>() {
+            return Some(JsonValue::NumberU64(n));
+        }
+        if let Ok(n) = literal.parse::<i64>() {
+            return Some(JsonValue::NumberI64(n));
+        }
+    }
+    // out of range exponent like 1e400 would become inf which JSON can't represent
+    literal
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .map(JsonValue::NumberF64)
+}
+
+pub fn parse_number<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonValue<'a>, E> {
+    parse_number_with(ParseOptions::default())(i)
+}
+
+pub fn parse_number_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue<'a>, E> {
+    move |i| {
+        let (rest, literal) = parse_number_literal(i)?;
+        match number_from_literal(literal, options.number_mode) {
+            Some(value) => Ok((rest, value)),
+            None => Err(nom::Err::Failure(E::add_context(
+                i,
+                "a number in f64 range",
+                E::from_error_kind(i, ErrorKind::Float),
+            ))),
+        }
+    }
+}
+
+/// a string, or in JSON5 also an identifier or a single quoted string
+pub(crate) fn parse_key_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    dialect: Dialect,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Cow<'a, str>, E> {
+    move |i| match dialect {
+        Dialect::Json | Dialect::Jsonc => context("a string key", parse_string)(i),
+        Dialect::Json5 => context("a key", parse_json5_key)(i),
+    }
+}
+
+/// same as [`cut`] for an error a parser already returned
+fn cut_err<E>(e: nom::Err<E>) -> nom::Err<E> {
+    match e {
+        nom::Err::Error(e) => nom::Err::Failure(e),
+        e => e,
+    }
+}
+
+/// the whitespace and comments around the tokens of an array item or object member
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Trivia<'a> {
+    pub before: &'a str,
+    /// around the `:` of a member, empty for an item
+    pub before_colon: &'a str,
+    pub after_colon: &'a str,
+    /// between the value and the `,` or the closing bracket
+    pub after: &'a str,
+    pub comma: bool,
+}
+
+/// What the grammar below builds while it reads a document: the [`JsonValue`] tree, the
+/// spanned and lossless trees, the events or the recovering parse.
+///
+/// `start` is the input at the first char of a node or key and `rest` the input after it. On a
+/// syntax error the grammar calls a `recover*` method, by default it returns the error and the
+/// parse stops there
+pub(crate) trait JsonBuilder<'a, E> {
+    type Value;
+    type Array;
+    type Map;
+    type Key;
+
+    fn scalar(&mut self, start: &'a str, value: JsonValue<'a>, rest: &'a str) -> Self::Value;
+    fn start_array(&mut self, start: &'a str) -> Self::Array;
+    fn item(&mut self, array: &mut Self::Array, value: Self::Value, trivia: Trivia<'a>);
+    /// `close` is the trivia before `]` when the last item has no `,` keeping it
+    fn end_array(&mut self, array: Self::Array, close: &'a str, rest: &'a str) -> Self::Value;
+    fn start_map(&mut self, start: &'a str) -> Self::Map;
+    /// called before the value of the key is read
+    fn key(
+        &mut self,
+        map: &mut Self::Map,
+        start: &'a str,
+        key: Cow<'a, str>,
+        rest: &'a str,
+    ) -> Result<Self::Key, nom::Err<E>>;
+    fn member(
+        &mut self,
+        map: &mut Self::Map,
+        key: Self::Key,
+        value: Self::Value,
+        trivia: Trivia<'a>,
+    );
+    fn end_map(
+        &mut self,
+        map: Self::Map,
+        close: &'a str,
+        rest: &'a str,
+    ) -> Result<Self::Value, nom::Err<E>>;
+
+    /// an error the grammar can go on after, e.g. a scalar root in [`RootMode::Rfc4627`]
+    fn recover(&mut self, e: nom::Err<E>) -> Result<(), nom::Err<E>> {
+        Err(e)
+    }
+
+    /// a missing or malformed value at `i`, return the input after it and a placeholder
+    fn recover_value(&mut self, _i: &'a str, e: nom::Err<E>) -> IResult<&'a str, Self::Value, E> {
+        Err(e)
+    }
+
+    /// a missing or malformed key at `i`, return the input where the `,` or `}` should be
+    fn recover_key(&mut self, _i: &'a str, e: nom::Err<E>) -> Result<&'a str, nom::Err<E>> {
+        Err(e)
+    }
+
+    /// no `:` at `i`, `Some` is the value of the member when there is no value to read either
+    fn recover_colon(
+        &mut self,
+        _i: &'a str,
+        e: nom::Err<E>,
+    ) -> Result<Option<Self::Value>, nom::Err<E>> {
+        Err(e)
+    }
+
+    /// no `,` or `closer` at `i`, return the input of the next entry and `,`, or the input
+    /// after the container and `closer`
+    fn recover_separator(
+        &mut self,
+        _i: &'a str,
+        _closer: char,
+        e: nom::Err<E>,
+    ) -> IResult<&'a str, char, E> {
+        Err(e)
+    }
+}
+
+/// builds the [`JsonValue`] tree
+struct TreeBuilder {
+    policy: DuplicateKeyPolicy,
+}
+
+impl<'a, E: ParseError<&'a str> + ContextError<&'a str>> JsonBuilder<'a, E> for TreeBuilder {
+    type Value = JsonValue<'a>;
+    type Array = Vec<JsonValue<'a>>;
+    /// the entries and where their keys start, to point at a duplicate
+    type Map = (Vec<(Cow<'a, str>, JsonValue<'a>)>, Vec<&'a str>);
+    type Key = (&'a str, Cow<'a, str>);
+
+    fn scalar(&mut self, _start: &'a str, value: JsonValue<'a>, _rest: &'a str) -> Self::Value {
+        value
+    }
+
+    fn start_array(&mut self, _start: &'a str) -> Self::Array {
+        Vec::new()
+    }
+
+    fn item(&mut self, array: &mut Self::Array, value: Self::Value, _trivia: Trivia<'a>) {
+        array.push(value);
+    }
+
+    fn end_array(&mut self, array: Self::Array, _close: &'a str, _rest: &'a str) -> Self::Value {
+        JsonValue::Array(array)
+    }
+
+    fn start_map(&mut self, _start: &'a str) -> Self::Map {
+        (Vec::new(), Vec::new())
+    }
+
+    fn key(
+        &mut self,
+        _map: &mut Self::Map,
+        start: &'a str,
+        key: Cow<'a, str>,
+        _rest: &'a str,
+    ) -> Result<Self::Key, nom::Err<E>> {
+        Ok((start, key))
+    }
+
+    fn member(&mut self, map: &mut Self::Map, key: Self::Key, value: Self::Value, _: Trivia<'a>) {
+        map.0.push((key.1, value));
+        map.1.push(key.0);
+    }
+
+    fn end_map(
+        &mut self,
+        (entries, positions): Self::Map,
+        _close: &'a str,
+        _rest: &'a str,
+    ) -> Result<Self::Value, nom::Err<E>> {
+        match JsonMap::from_entries_with(entries, self.policy) {
+            Ok(map) => Ok(JsonValue::Map(map)),
+            Err(duplicate) => Err(duplicate_key(positions[duplicate])),
+        }
+    }
+}
+
+/// the error of a repeated key starting at `pos` with [`DuplicateKeyPolicy::Error`]
+pub(crate) fn duplicate_key<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    pos: &'a str,
+) -> nom::Err<E> {
+    nom::Err::Failure(E::add_context(
+        pos,
+        "a unique key",
+        E::from_error_kind(pos, ErrorKind::Verify),
     ))
 }
 
+/// [`split_with`] for the grammar, an unclosed comment is recovered as running to the end
+fn build_trivia<'a, E, B>(i: &'a str, dialect: Dialect, b: &mut B) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    B: JsonBuilder<'a, E>,
+{
+    match split_with(dialect)(i) {
+        Err(e) => {
+            b.recover(e)?;
+            Ok((&i[i.len()..], i))
+        }
+        parsed => parsed,
+    }
+}
+
+/// the input after `closer` when the container ends before its next entry: right after `{` or
+/// `[`, or after a trailing `,` the dialect allows
+fn closed_before_entry(i: &str, closer: char, first: bool, dialect: Dialect) -> Option<&str> {
+    i.strip_prefix(closer)
+        .filter(|_| first || dialect != Dialect::Json)
+}
+
+/// the trivia and the `,` or `closer` after an entry, `true` if the container ends there
+fn build_separator<'a, E, B>(
+    i: &'a str,
+    closer: char,
+    dialect: Dialect,
+    b: &mut B,
+) -> IResult<&'a str, (&'a str, bool), E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    B: JsonBuilder<'a, E>,
+{
+    let (i, after) = build_trivia(i, dialect, b)?;
+    let expected = if closer == '}' {
+        "`,` or `}`"
+    } else {
+        "`,` or `]`"
+    };
+    let (rest, separator) = match cut(context(expected, alt((char_(','), char_(closer)))))(i) {
+        Ok(parsed) => parsed,
+        Err(e) => b.recover_separator(i, closer, e)?,
+    };
+    Ok((rest, (after, separator == closer)))
+}
+
+/// The one grammar of JSON values, generic over what it builds. The input starts at the value,
+/// its leading trivia belongs to the caller
+pub(crate) fn build_json_value<'a, E, B>(
+    i: &'a str,
+    options: ParseOptions,
+    b: &mut B,
+) -> IResult<&'a str, B::Value, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    B: JsonBuilder<'a, E>,
+{
+    match i.chars().next() {
+        Some('{') => build_json_map(i, options, b),
+        Some('[') => build_json_array(i, options, b),
+        _ => match context("a JSON value", parse_scalar_with(options))(i) {
+            Ok((rest, value)) => Ok((rest, b.scalar(i, value, rest))),
+            Err(e) => b.recover_value(i, e),
+        },
+    }
+}
+
+pub(crate) fn build_json_map<'a, E, B>(
+    start: &'a str,
+    options: ParseOptions,
+    b: &mut B,
+) -> IResult<&'a str, B::Value, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    B: JsonBuilder<'a, E>,
+{
+    let (mut i, _) = char_('{')(start)?;
+    let mut map = b.start_map(start);
+    let mut first = true;
+    loop {
+        let (key_start, before) = build_trivia(i, options.dialect, b)?;
+        if let Some(rest) = closed_before_entry(key_start, '}', first, options.dialect) {
+            let value = b.end_map(map, before, rest)?;
+            return Ok((rest, value));
+        }
+        first = false;
+        let (rest, member) = match cut(parse_key_with(options.dialect))(key_start) {
+            Ok((rest, key)) => {
+                let key = b.key(&mut map, key_start, key, rest)?;
+                let (rest, before_colon) = build_trivia(rest, options.dialect, b)?;
+                let (rest, after_colon, value) = match cut(context("`:`", char_(':')))(rest) {
+                    Ok((rest, _)) => {
+                        let (rest, after_colon) = build_trivia(rest, options.dialect, b)?;
+                        let (rest, value) = build_json_value(rest, options, b).map_err(cut_err)?;
+                        (rest, after_colon, value)
+                    }
+                    Err(e) => match b.recover_colon(rest, e)? {
+                        Some(value) => (rest, "", value),
+                        None => {
+                            let (rest, value) =
+                                build_json_value(rest, options, b).map_err(cut_err)?;
+                            (rest, "", value)
+                        }
+                    },
+                };
+                (rest, Some((key, before_colon, after_colon, value)))
+            }
+            Err(e) => (b.recover_key(key_start, e)?, None),
+        };
+        let (rest, (after, closed)) = build_separator(rest, '}', options.dialect, b)?;
+        if let Some((key, before_colon, after_colon, value)) = member {
+            let trivia = Trivia {
+                before,
+                before_colon,
+                after_colon,
+                after,
+                comma: !closed,
+            };
+            b.member(&mut map, key, value, trivia);
+        }
+        if closed {
+            let value = b.end_map(map, "", rest)?;
+            return Ok((rest, value));
+        }
+        i = rest;
+    }
+}
+
+pub(crate) fn build_json_array<'a, E, B>(
+    start: &'a str,
+    options: ParseOptions,
+    b: &mut B,
+) -> IResult<&'a str, B::Value, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    B: JsonBuilder<'a, E>,
+{
+    let (mut i, _) = char_('[')(start)?;
+    let mut array = b.start_array(start);
+    let mut first = true;
+    loop {
+        let (value_start, before) = build_trivia(i, options.dialect, b)?;
+        if let Some(rest) = closed_before_entry(value_start, ']', first, options.dialect) {
+            return Ok((rest, b.end_array(array, before, rest)));
+        }
+        if let (false, Some(rest)) = (first, value_start.strip_prefix(']')) {
+            // JSON has no trailing `,`, the value after it is missing and nothing is added
+            b.recover(nom::Err::Failure(E::add_context(
+                value_start,
+                "a JSON value",
+                E::from_error_kind(value_start, ErrorKind::Char),
+            )))?;
+            return Ok((rest, b.end_array(array, before, rest)));
+        }
+        first = false;
+        let (rest, value) = build_json_value(value_start, options, b).map_err(cut_err)?;
+        let (rest, (after, closed)) = build_separator(rest, ']', options.dialect, b)?;
+        let trivia = Trivia {
+            before,
+            after,
+            comma: !closed,
+            ..Trivia::default()
+        };
+        b.item(&mut array, value, trivia);
+        if closed {
+            return Ok((rest, b.end_array(array, "", rest)));
+        }
+        i = rest;
+    }
+}
+
+/// a whole document: the trivia before the root value, the value and the trivia after it
+pub(crate) fn build_json_document<'a, E, B>(
+    i: &'a str,
+    options: ParseOptions,
+    b: &mut B,
+) -> IResult<&'a str, (&'a str, B::Value, &'a str), E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+    B: JsonBuilder<'a, E>,
+{
+    let (i, before) = build_trivia(i, options.dialect, b)?;
+    if options.root_mode == RootMode::Rfc4627 && !i.starts_with(['{', '[']) {
+        b.recover(nom::Err::Error(E::add_context(
+            i,
+            "`{` or `[`",
+            E::from_error_kind(i, ErrorKind::Char),
+        )))?;
+    }
+    let (i, value) = build_json_value(i, options, b)?;
+    let (i, after) = build_trivia(i, options.dialect, b)?;
+    Ok((i, (before, value, after)))
+}
+
+fn tree_builder(options: ParseOptions) -> TreeBuilder {
+    TreeBuilder {
+        policy: options.duplicate_key_policy,
+    }
+}
+
+pub fn parse_json_map<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonMap<'a>, E> {
+    parse_json_map_with(ParseOptions::default())(i)
+}
+
+pub fn parse_json_map_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonMap<'a>, E> {
+    move |i| {
+        let (rest, value) = build_json_map(i, options, &mut tree_builder(options))?;
+        let JsonValue::Map(map) = value else {
+            unreachable!("the tree of a map is a map")
+        };
+        Ok((rest, map))
+    }
+}
+
+pub fn parse_json_array<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Vec<JsonValue<'a>>, E> {
+    parse_json_array_with(ParseOptions::default())(i)
+}
+
+pub fn parse_json_array_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<JsonValue<'a>>, E> {
+    move |i| {
+        let (rest, value) = build_json_array(i, options, &mut tree_builder(options))?;
+        let JsonValue::Array(array) = value else {
+            unreachable!("the tree of an array is an array")
+        };
+        Ok((rest, array))
+    }
+}
+
+/// The root node of json tree can be any value, or only Map/Array in [`RootMode::Rfc4627`]
+pub fn parse_json_root<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    _i: &str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue<'a>, E> {
+    parse_json_root_with(ParseOptions::default())
+}
+
+pub fn parse_json_root_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue<'a>, E> {
+    move |i| match options.root_mode {
+        RootMode::AnyValue => parse_json_value_with(options)(i),
+        RootMode::Rfc4627 => context(
+            "`{` or `[`",
+            alt((
+                map(parse_json_map_with(options), JsonValue::Map),
+                map(parse_json_array_with(options), JsonValue::Array),
+            )),
+        )(i),
+    }
+}
+
 /// here, we apply the space parser before trying to parse a value
-fn parse_json_value<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, JsonValue, E> {
-    preceded(
-        split,
-        alt((
-            map(parse_json_root(i), |val| val),
+pub fn parse_json_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonValue<'a>, E> {
+    parse_json_value_with(ParseOptions::default())(i)
+}
+
+pub fn parse_json_value_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue<'a>, E> {
+    move |i| {
+        let (i, _) = split_with(options.dialect)(i)?;
+        build_json_value(i, options, &mut tree_builder(options))
+    }
+}
+
+/// null, boolean, number or string, the values that aren't containers
+pub(crate) fn parse_scalar_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue<'a>, E> {
+    move |i| {
+        let null_or_boolean = alt((
+            value(JsonValue::Null, tag("null")),
             map(
                 alt((value(true, tag("true")), value(false, tag("false")))),
                 JsonValue::Boolean,
             ),
-            map(double, JsonValue::NumberF64),
-            map(parse_string, |s| JsonValue::String(s.to_string())),
-        )),
-    )(i)
+        ));
+        match options.dialect {
+            Dialect::Json | Dialect::Jsonc => alt((
+                null_or_boolean,
+                parse_number_with(options),
+                map(parse_string, JsonValue::String),
+            ))(i),
+            Dialect::Json5 => alt((
+                null_or_boolean,
+                parse_json5_number_with(options),
+                map(parse_json5_string, JsonValue::String),
+            ))(i),
+        }
+    }
 }
 
 /// 因为 nom 类似 warp 函数签名一堆 impl trait 所以被迫函数式编程写法
-fn parse_json_str<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, JsonValue, E> {
-    delimited(split, map(parse_json_root(i), |val| val), split)(i)
+pub fn parse_json_str<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonValue<'a>, E> {
+    parse_json_str_with(ParseOptions::default())(i)
+}
+
+pub fn parse_json_str_with<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    options: ParseOptions,
+) -> impl FnMut(&'a str) -> IResult<&'a str, JsonValue<'a>, E> {
+    move |i| {
+        let (rest, (_, value, _)) = build_json_document(i, options, &mut tree_builder(options))?;
+        Ok((rest, value))
+    }
 }
 
 #[test]
@@ -126,6 +888,136 @@ fn test_json_value() {
     assert!(JsonValue::from_str(r#"[1, "two"]"#).is_ok());
     assert!(JsonValue::from_str("null").is_ok());
     assert!(JsonValue::from_str(r#"{"key": null}"#).is_ok());
-    assert!(JsonValue::from_str("{\"key\": \"???\"}").is_err());
-    assert!(JsonValue::from_str("{\"key\": \"中文\"}").is_err());
+    assert!(JsonValue::from_str("{\"key\": \"???\"}").is_ok());
+    assert!(JsonValue::from_str("{\"key\": \"中文\"}").is_ok());
+}
+
+#[test]
+fn test_json_root() {
+    assert_eq!(
+        JsonValue::from_str(r#" "abc" "#),
+        Ok(JsonValue::String("abc".into()))
+    );
+    assert_eq!(JsonValue::from_str("42"), Ok(JsonValue::NumberU64(42)));
+    assert_eq!(JsonValue::from_str("true"), Ok(JsonValue::Boolean(true)));
+
+    let legacy = ParseOptions {
+        root_mode: RootMode::Rfc4627,
+        ..ParseOptions::default()
+    };
+    assert!(JsonValue::from_str_with("{}", legacy).is_ok());
+    assert!(JsonValue::from_str_with(" [null] ", legacy).is_ok());
+    for scalar in ["null", "42", "true", r#""abc""#] {
+        let e = JsonValue::from_str_with(scalar, legacy).unwrap_err();
+        assert_eq!(e.expected(), Some("`{` or `[`"));
+    }
+}
+
+#[test]
+fn test_borrowed_value() {
+    let text = String::from(r#"{"plain": "abc", "escaped": "a\nb"}"#);
+    let value = JsonValue::from_str(&text).unwrap();
+    let JsonValue::Map(map) = value.clone() else {
+        unreachable!()
+    };
+    let entries = map.into_iter().collect::<Vec<_>>();
+    assert!(entries.iter().all(|(k, _)| matches!(k, Cow::Borrowed(_))));
+    assert!(matches!(
+        &entries[0].1,
+        JsonValue::String(Cow::Borrowed("abc"))
+    ));
+    assert!(matches!(&entries[1].1, JsonValue::String(Cow::Owned(s)) if s == "a\nb"));
+
+    let owned: JsonValue<'static> = value.into_owned();
+    drop(text);
+    assert_eq!(
+        owned,
+        r#"{"plain": "abc", "escaped": "a\nb"}"#.parse::<JsonValue>().unwrap()
+    );
+}
+
+#[test]
+fn test_trailing_input() {
+    assert!(JsonValue::from_str(" {\"a\": 1} \r\n").is_ok());
+    let e = JsonValue::from_str(r#"{"a": 1} junk"#).unwrap_err();
+    assert_eq!((e.column(), e.expected()), (10, Some("end of input")));
+    assert!(JsonValue::from_str("[] []").is_err());
+
+    let (first, rest) = JsonValue::parse_prefix(r#"{"a": 1} [2]"#).unwrap();
+    assert_eq!(
+        first,
+        JsonValue::Map(
+            [("a".to_string(), JsonValue::NumberU64(1))]
+                .into_iter()
+                .collect()
+        )
+    );
+    assert_eq!(rest, "[2]");
+    let (second, rest) = JsonValue::parse_prefix(rest).unwrap();
+    assert_eq!(second, JsonValue::Array(vec![JsonValue::NumberU64(2)]));
+    assert_eq!(rest, "");
+}
+
+#[test]
+fn test_parse_number() {
+    let parse = |s| parse_number::<(&str, ErrorKind)>(s).map(|(_, n)| n);
+    assert_eq!(parse("0"), Ok(JsonValue::NumberU64(0)));
+    match parse("-0") {
+        Ok(JsonValue::NumberF64(n)) => assert!(n == 0.0 && n.is_sign_negative()),
+        other => panic!("{:?}", other),
+    }
+    assert_eq!(JsonValue::from_str("[-0]").unwrap().to_string(), "[-0.0]");
+    let precise = ParseOptions {
+        number_mode: NumberMode::ArbitraryPrecision,
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        JsonValue::from_str_with("-0", precise),
+        Ok(JsonValue::Number(RawNumber("-0".to_string())))
+    );
+    assert_eq!(parse("-42"), Ok(JsonValue::NumberI64(-42)));
+    assert_eq!(
+        parse("18446744073709551615"),
+        Ok(JsonValue::NumberU64(u64::MAX))
+    );
+    assert_eq!(
+        parse("-9223372036854775808"),
+        Ok(JsonValue::NumberI64(i64::MIN))
+    );
+    assert_eq!(
+        parse("18446744073709551616"),
+        Ok(JsonValue::NumberF64(18446744073709551616.0))
+    );
+    assert_eq!(parse("1.5"), Ok(JsonValue::NumberF64(1.5)));
+    assert_eq!(parse("-1.5E+2"), Ok(JsonValue::NumberF64(-150.0)));
+    assert_eq!(parse("2e-1"), Ok(JsonValue::NumberF64(0.2)));
+    for invalid in ["+1", ".5", "-", "inf", "NaN", "1e400"] {
+        assert!(parse(invalid).is_err(), "{}", invalid);
+    }
+    for invalid in ["007", "1.", "1e", "1.e5"] {
+        assert!(
+            JsonValue::from_str(&format!("[{}]", invalid)).is_err(),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_parse_string() {
+    let parse = |s| parse_string::<(&str, ErrorKind)>(s).map(|(_, s)| s.into_owned());
+    assert_eq!(parse(r#""""#), Ok(String::new()));
+    assert_eq!(parse(r#""hello world""#), Ok("hello world".to_string()));
+    assert_eq!(parse(r#""a-b 中文""#), Ok("a-b 中文".to_string()));
+    assert_eq!(
+        parse(r#""\" \\ \/ \b \f \n \r \t""#),
+        Ok("\" \\ / \u{08} \u{0C} \n \r \t".to_string())
+    );
+    assert_eq!(parse(r#""\u00e9\u4E2D""#), Ok("é中".to_string()));
+    assert_eq!(parse(r#""\uD83D\uDE00""#), Ok("😀".to_string()));
+    assert!(parse(r#""\uD83D""#).is_err());
+    assert!(parse(r#""\uDE00""#).is_err());
+    assert!(parse(r#""\x""#).is_err());
+    assert!(parse("\"tab\there\"").is_err());
+    assert!(parse(r#""unterminated"#).is_err());
 }
@@ -0,0 +1,499 @@
+use crate::json_map::JsonMap;
+use crate::json_path::values_equal;
+use crate::json_pointer::{JsonPointer, PointerError};
+use crate::JsonValue;
+use std::borrow::Cow;
+use std::fmt;
+
+/// why a JSON Patch can't be applied, `index` is the position of the failing operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// a JSON Patch is an array of operation objects
+    NotAnArray,
+    /// the operation is malformed, e.g. an unknown `op` or a missing `value`
+    Invalid { index: usize, reason: &'static str },
+    /// `path` or `from` doesn't resolve
+    Pointer { index: usize, error: PointerError },
+    /// a `test` operation found a different value
+    TestFailed { index: usize },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::NotAnArray => f.write_str("a JSON patch must be an array of operations"),
+            PatchError::Invalid { index, reason } => write!(f, "operation {}: {}", index, reason),
+            PatchError::Pointer { index, error } => write!(f, "operation {}: {}", index, error),
+            PatchError::TestFailed { index } => write!(f, "operation {}: test failed", index),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// one decoded operation of an RFC 6902 patch
+enum Operation<'p, 'a> {
+    Add(JsonPointer, &'p JsonValue<'a>),
+    Remove(JsonPointer),
+    Replace(JsonPointer, &'p JsonValue<'a>),
+    Move {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    Copy {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    Test(JsonPointer, &'p JsonValue<'a>),
+}
+
+impl<'p, 'a> Operation<'p, 'a> {
+    fn decode(index: usize, operation: &'p JsonValue<'a>) -> Result<Self, PatchError> {
+        let invalid = |reason| PatchError::Invalid { index, reason };
+        let JsonValue::Map(operation) = operation else {
+            return Err(invalid("an operation must be an object"));
+        };
+        let pointer = |member, missing| match operation.get(member) {
+            Some(JsonValue::String(pointer)) => pointer
+                .parse::<JsonPointer>()
+                .map_err(|error| PatchError::Pointer { index, error }),
+            _ => Err(invalid(missing)),
+        };
+        let value = || operation.get("value").ok_or(invalid("missing `value`"));
+        let path = || pointer("path", "missing `path`");
+        let from = || pointer("from", "missing `from`");
+        Ok(match operation.get("op") {
+            Some(JsonValue::String(op)) => match op.as_ref() {
+                "add" => Operation::Add(path()?, value()?),
+                "remove" => Operation::Remove(path()?),
+                "replace" => Operation::Replace(path()?, value()?),
+                "move" => Operation::Move {
+                    from: from()?,
+                    path: path()?,
+                },
+                "copy" => Operation::Copy {
+                    from: from()?,
+                    path: path()?,
+                },
+                "test" => Operation::Test(path()?, value()?),
+                _ => return Err(invalid("unknown `op`")),
+            },
+            _ => return Err(invalid("missing `op`")),
+        })
+    }
+
+    fn apply(self, index: usize, doc: &mut JsonValue<'a>) -> Result<(), PatchError> {
+        let pointer_error = |error| PatchError::Pointer { index, error };
+        match self {
+            Operation::Add(path, value) => {
+                path.insert(doc, value.clone()).map_err(pointer_error)?;
+            }
+            Operation::Remove(path) => {
+                path.remove(doc).map_err(pointer_error)?;
+            }
+            Operation::Replace(path, value) => {
+                *path.resolve_mut(doc).map_err(pointer_error)? = value.clone();
+            }
+            Operation::Move { from, path } => {
+                if path.tokens().len() > from.tokens().len()
+                    && path.tokens().starts_with(from.tokens())
+                {
+                    return Err(PatchError::Invalid {
+                        index,
+                        reason: "can't move a value into one of its children",
+                    });
+                }
+                let value = from.remove(doc).map_err(pointer_error)?;
+                path.insert(doc, value).map_err(pointer_error)?;
+            }
+            Operation::Copy { from, path } => {
+                let value = from.resolve(doc).map_err(pointer_error)?.clone();
+                path.insert(doc, value).map_err(pointer_error)?;
+            }
+            Operation::Test(path, value) => {
+                if !values_equal(path.resolve(doc).map_err(pointer_error)?, value) {
+                    return Err(PatchError::TestFailed { index });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// apply an RFC 6902 JSON Patch, an array of `add`, `remove`, `replace`, `move`, `copy` and
+/// `test` operations. The patch is atomic, `doc` is left untouched if any operation fails
+pub fn apply_patch<'a>(doc: &mut JsonValue<'a>, patch: &JsonValue<'a>) -> Result<(), PatchError> {
+    let JsonValue::Array(operations) = patch else {
+        return Err(PatchError::NotAnArray);
+    };
+    let mut patched = doc.clone();
+    for (index, operation) in operations.iter().enumerate() {
+        Operation::decode(index, operation)?.apply(index, &mut patched)?;
+    }
+    *doc = patched;
+    Ok(())
+}
+
+/// apply an RFC 7396 JSON Merge Patch: objects are merged recursively, a `null` member removes
+/// the key and any other value replaces the target
+pub fn apply_merge_patch<'a>(doc: &mut JsonValue<'a>, patch: &JsonValue<'a>) {
+    let JsonValue::Map(patch) = patch else {
+        *doc = patch.clone();
+        return;
+    };
+    if !matches!(doc, JsonValue::Map(_)) {
+        *doc = JsonValue::Map(JsonMap::new());
+    }
+    let JsonValue::Map(map) = doc else {
+        unreachable!()
+    };
+    for (key, value) in patch.iter() {
+        if *value == JsonValue::Null {
+            map.remove(key);
+            continue;
+        }
+        if !map.contains_key(key) {
+            map.insert(key.to_string(), JsonValue::Null);
+        }
+        apply_merge_patch(map.get_mut(key).expect("inserted above"), value);
+    }
+}
+
+fn operation<'a>(
+    op: &'static str,
+    path: &JsonPointer,
+    value: Option<JsonValue<'a>>,
+) -> JsonValue<'a> {
+    let mut operation = JsonMap::new();
+    operation.insert("op", JsonValue::String(op.into()));
+    operation.insert("path", JsonValue::String(Cow::Owned(path.to_string())));
+    if let Some(value) = value {
+        operation.insert("value", value);
+    }
+    JsonValue::Map(operation)
+}
+
+/// edit script turning `a` into `b` from their longest common subsequence
+enum Edit {
+    Keep,
+    Remove,
+    /// insert `b[index]`
+    Add(usize),
+}
+
+/// the LCS table of the changed middle of two arrays is only built below this many cells,
+/// larger arrays are compared position by position
+const LCS_LIMIT: usize = 1 << 20;
+
+fn array_edits(a: &[JsonValue<'_>], b: &[JsonValue<'_>]) -> Vec<Edit> {
+    let prefix = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let mut edits = Vec::new();
+    edits.extend((0..prefix).map(|_| Edit::Keep));
+    if a_mid.len().saturating_mul(b_mid.len()) > LCS_LIMIT {
+        // a removal followed by an addition is diffed as a change of that item
+        for i in 0..a_mid.len().max(b_mid.len()) {
+            if i < a_mid.len() {
+                edits.push(Edit::Remove);
+            }
+            if i < b_mid.len() {
+                edits.push(Edit::Add(prefix + i));
+            }
+        }
+    } else {
+        // lcs[i][j] is the LCS length of a_mid[i..] and b_mid[j..]
+        let mut lcs = vec![vec![0usize; b_mid.len() + 1]; a_mid.len() + 1];
+        for i in (0..a_mid.len()).rev() {
+            for j in (0..b_mid.len()).rev() {
+                lcs[i][j] = if a_mid[i] == b_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a_mid.len() || j < b_mid.len() {
+            if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+                edits.push(Edit::Keep);
+                (i, j) = (i + 1, j + 1);
+            } else if i < a_mid.len() && (j == b_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                edits.push(Edit::Remove);
+                i += 1;
+            } else {
+                edits.push(Edit::Add(prefix + j));
+                j += 1;
+            }
+        }
+    }
+    edits.extend((0..suffix).map(|_| Edit::Keep));
+    edits
+}
+
+fn diff_into<'a>(
+    a: &JsonValue<'_>,
+    b: &JsonValue<'a>,
+    path: &mut JsonPointer,
+    patch: &mut Vec<JsonValue<'a>>,
+) {
+    match (a, b) {
+        _ if a == b => {}
+        (JsonValue::Map(a), JsonValue::Map(b)) => {
+            for key in a.keys().filter(|key| !b.contains_key(key)) {
+                path.push(key);
+                patch.push(operation("remove", path, None));
+                path.pop();
+            }
+            // key order isn't significant, kept keys are diffed wherever they are
+            for (key, value) in b.iter() {
+                path.push(key);
+                match a.get(key) {
+                    Some(old) => diff_into(old, value, path, patch),
+                    None => patch.push(operation("add", path, Some(value.clone()))),
+                }
+                path.pop();
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            // `index` is the position in the array as it is after the previous operations
+            let mut index = 0;
+            let mut old = a.iter();
+            let mut edits = array_edits(a, b).into_iter().peekable();
+            while let Some(edit) = edits.next() {
+                match edit {
+                    Edit::Keep => {
+                        old.next();
+                        index += 1;
+                    }
+                    Edit::Remove => {
+                        let removed = old.next().expect("one removed element per edit");
+                        path.push(index.to_string());
+                        // a removal followed by an insertion at the same place is a change
+                        if let Some(&Edit::Add(j)) = edits.peek() {
+                            edits.next();
+                            diff_into(removed, &b[j], path, patch);
+                            index += 1;
+                        } else {
+                            patch.push(operation("remove", path, None));
+                        }
+                        path.pop();
+                    }
+                    Edit::Add(j) => {
+                        path.push(index.to_string());
+                        patch.push(operation("add", path, Some(b[j].clone())));
+                        path.pop();
+                        index += 1;
+                    }
+                }
+            }
+        }
+        _ => patch.push(operation("replace", path, Some(b.clone()))),
+    }
+}
+
+/// an RFC 6902 patch that turns `a` into `b`: unchanged subtrees produce no operation, objects
+/// are compared key by key ignoring their order like `==` does, and arrays by their longest
+/// common subsequence, or item by item when they are very long
+pub fn diff<'a>(a: &JsonValue<'_>, b: &JsonValue<'a>) -> JsonValue<'a> {
+    let mut patch = Vec::new();
+    diff_into(a, b, &mut JsonPointer::root(), &mut patch);
+    JsonValue::Array(patch)
+}
+
+#[test]
+fn test_json_patch() {
+    let parse = |text: &'static str| JsonValue::from_str(text).unwrap();
+
+    // RFC 6902 appendix A
+    for (doc, patch, expected) in [
+        (
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+            r#"{"baz":"qux","foo":"bar"}"#,
+        ),
+        (
+            r#"{"foo": ["bar", "baz"]}"#,
+            r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#,
+            r#"{"foo":["bar","qux","baz"]}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "remove", "path": "/baz"}]"#,
+            r#"{"foo":"bar"}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#,
+            r#"{"baz":"boo","foo":"bar"}"#,
+        ),
+        (
+            r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+            r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+            r#"{"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}}"#,
+        ),
+        (
+            r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+            r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#,
+            r#"{"foo":["all","cows","eat","grass"]}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+            r#"[{"op": "test", "path": "/baz", "value": "qux"}, {"op": "test", "path": "/foo/1", "value": 2.0}]"#,
+            r#"{"baz":"qux","foo":["a",2,"c"]}"#,
+        ),
+        (
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/child", "value": {"grandchild": {}}}]"#,
+            r#"{"foo":"bar","child":{"grandchild":{}}}"#,
+        ),
+        (
+            r#"{"foo": ["bar"]}"#,
+            r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#,
+            r#"{"foo":["bar",["abc","def"]]}"#,
+        ),
+        (
+            r#"{"a": 1}"#,
+            r#"[{"op": "copy", "from": "/a", "path": "/b"}]"#,
+            r#"{"a":1,"b":1}"#,
+        ),
+    ] {
+        let mut doc = parse(doc);
+        apply_patch(&mut doc, &parse(patch)).unwrap();
+        assert_eq!(doc, parse(expected), "{}", patch);
+    }
+
+    let mut doc = parse(r#"{"baz": "qux", "foo": "bar"}"#);
+    let original = doc.clone();
+    for (patch, error) in [
+        (r#"{}"#, PatchError::NotAnArray),
+        (
+            r#"[{"op": "replace", "path": "/foo", "value": 1}, {"op": "test", "path": "/baz", "value": "bar"}]"#,
+            PatchError::TestFailed { index: 1 },
+        ),
+        (
+            r#"[{"op": "add", "path": "/baz/bat", "value": "qux"}]"#,
+            PatchError::Pointer {
+                index: 0,
+                error: PointerError::NotAContainer {
+                    pointer: "/baz/bat".to_string(),
+                },
+            },
+        ),
+        (
+            r#"[{"op": "move", "from": "/foo", "path": "/foo/x"}]"#,
+            PatchError::Invalid {
+                index: 0,
+                reason: "can't move a value into one of its children",
+            },
+        ),
+        (
+            r#"[{"op": "add", "path": "/x"}]"#,
+            PatchError::Invalid {
+                index: 0,
+                reason: "missing `value`",
+            },
+        ),
+        (
+            r#"[{"op": "frobnicate", "path": "/x"}]"#,
+            PatchError::Invalid {
+                index: 0,
+                reason: "unknown `op`",
+            },
+        ),
+    ] {
+        assert_eq!(apply_patch(&mut doc, &parse(patch)), Err(error));
+        assert_eq!(doc, original);
+    }
+
+    // RFC 7396 appendix A
+    for (doc, patch, expected) in [
+        (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+        (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+        (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+        (
+            r#"{"a":{"b": "c"}}"#,
+            r#"{"a":{"b":"d","c":null}}"#,
+            r#"{"a":{"b":"d"}}"#,
+        ),
+        (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+        (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+        (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+        (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+        (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+        (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+        (
+            r#"{}"#,
+            r#"{"a":{"bb":{"ccc":null}}}"#,
+            r#"{"a":{"bb":{}}}"#,
+        ),
+    ] {
+        let mut doc = parse(doc);
+        apply_merge_patch(&mut doc, &parse(patch));
+        assert_eq!(doc, parse(expected), "{}", patch);
+    }
+
+    for (a, b, operations) in [
+        (
+            r#"{"a": 1, "b": [1, 2, 3], "c": {"d": 1}}"#,
+            r#"{"a": 1, "b": [1, 3, 4], "c": {"d": 2}, "e": null}"#,
+            4,
+        ),
+        (r#"[1, 2, 3, 4, 5]"#, r#"[0, 1, 3, 5, 6]"#, 4),
+        (
+            r#"[{"id": 1, "x": 1}, {"id": 2}]"#,
+            r#"[{"id": 1, "x": 2}, {"id": 2}]"#,
+            1,
+        ),
+        (r#"{"a/b": {"~": 1}}"#, r#"{"a/b": {"~": 2}}"#, 1),
+        (r#"[]"#, r#"[1, 2]"#, 2),
+        (r#"[1, 2]"#, r#"[]"#, 2),
+        (r#"1"#, r#"{"a": 1}"#, 1),
+        (r#"{"a": [1]}"#, r#"{"a": [1]}"#, 0),
+        (r#"{"a": 1, "b": 2}"#, r#"{"b": 2, "a": 1}"#, 0),
+        (
+            r#"{"a": 1, "b": 2, "c": 3}"#,
+            r#"{"a": 1, "c": 3, "d": 4, "b": 5}"#,
+            2,
+        ),
+        (r#"[{"a": 1, "b": 2}]"#, r#"[{"b": 2, "a": 1}]"#, 0),
+    ] {
+        let (a, b) = (parse(a), parse(b));
+        let patch = diff(&a, &b);
+        let JsonValue::Array(list) = &patch else {
+            unreachable!()
+        };
+        assert_eq!(list.len(), operations, "{}", patch);
+        let mut patched = a.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched, b, "{}", patch);
+    }
+
+    // past the LCS limit the changed middle is diffed item by item
+    let a = JsonValue::Array((0..5000).map(JsonValue::from).collect());
+    let b = JsonValue::Array((0..5000).map(|i| JsonValue::from(i * 2)).collect());
+    let patch = diff(&a, &b);
+    let mut patched = a.clone();
+    apply_patch(&mut patched, &patch).unwrap();
+    assert_eq!(patched, b);
+    let JsonValue::Array(list) = &patch else {
+        unreachable!()
+    };
+    assert_eq!(list.len(), 4999);
+    assert_eq!(
+        diff(
+            &parse(r#"{"a/b": {"~": 1}}"#),
+            &parse(r#"{"a/b": {"~": 2}}"#)
+        )
+        .to_string(),
+        r#"[{"op":"replace","path":"/a~1b/~0","value":2}]"#
+    );
+}
@@ -0,0 +1,735 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_parser::{parse_number, parse_quoted_string, split};
+use crate::JsonValue;
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while},
+    character::complete::{char as char_, digit0, one_of, satisfy},
+    combinator::{cut, eof, map, map_opt, opt, recognize, value},
+    error::{context, ContextError, ErrorKind, ParseError},
+    multi::{many0, separated_list1},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// RFC 9535 JSONPath query such as `$.store.book[?@.price < 10].title`.
+///
+/// Filters support comparisons, `&&`, `||`, `!`, existence tests and the `length`, `count` and
+/// `value` functions. `match` and `search` need an I-Regexp engine and are not supported
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// `.name`, `.*` or `[...]`, select among the children of each node
+    Child(Vec<Selector>),
+    /// `..name`, `..*` or `..[...]`, select among the children of each node and its descendants
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    /// negative index count from the end of the array
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Box<Filter>),
+}
+
+/// logical expression of a `?` filter selector, evaluated with `@` as each child in turn
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Or(Vec<Filter>),
+    And(Vec<Filter>),
+    Not(Box<Filter>),
+    /// true if the query select at least one node
+    Exists(Query),
+    Compare(Comparable, CompareOp, Comparable),
+}
+
+/// `@...` relative to the current node of a filter or `$...` from the root
+#[derive(Debug, Clone, PartialEq)]
+struct Query {
+    relative: bool,
+    path: JsonPath,
+}
+
+/// one side of a comparison, it evaluates to a value or to Nothing
+#[derive(Debug, Clone, PartialEq)]
+enum Comparable {
+    Literal(JsonValue<'static>),
+    /// a singular query, Nothing unless it select exactly one node
+    Query(Query),
+    Length(Box<Comparable>),
+    Count(Query),
+    Value(Query),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Query {
+    /// only name and index child selectors, so at most one node is selected
+    fn is_singular(&self) -> bool {
+        self.path.segments.iter().all(|segment| match segment {
+            Segment::Child(selectors) => {
+                matches!(selectors[..], [Selector::Name(_) | Selector::Index(_)])
+            }
+            Segment::Descendant(_) => false,
+        })
+    }
+}
+
+/// `0` or a non-zero integer without leading zero, within the I-JSON exact range ±(2^53-1)
+fn parse_int<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, i64, E> {
+    const MAX: i64 = (1 << 53) - 1;
+    map_opt(
+        alt((
+            tag("0"),
+            recognize(tuple((opt(char_('-')), one_of("123456789"), digit0))),
+        )),
+        |int: &str| int.parse::<i64>().ok().filter(|n| (-MAX..=MAX).contains(n)),
+    )(i)
+}
+
+/// shorthand member name after `.`, e.g. `.store`
+fn parse_member_name<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    fn is_name_first(c: char) -> bool {
+        c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+    }
+    recognize(pair(
+        satisfy(is_name_first),
+        take_while(|c: char| is_name_first(c) || c.is_ascii_digit()),
+    ))(i)
+}
+
+/// JSONPath strings can be single or double quoted
+fn parse_string_literal<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    alt((parse_quoted_string('"'), parse_quoted_string('\'')))(i)
+}
+
+/// `start:end:step`, every part is optional
+fn parse_slice<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Selector, E> {
+    map(
+        tuple((
+            opt(terminated(parse_int, split)),
+            char_(':'),
+            split,
+            opt(terminated(parse_int, split)),
+            opt(preceded(char_(':'), opt(preceded(split, parse_int)))),
+        )),
+        |(start, _, _, end, step)| Selector::Slice {
+            start,
+            end,
+            step: step.flatten(),
+        },
+    )(i)
+}
+
+fn parse_selector<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Selector, E> {
+    alt((
+        map(parse_string_literal, |name| {
+            Selector::Name(name.into_owned())
+        }),
+        value(Selector::Wildcard, char_('*')),
+        parse_slice,
+        map(parse_int, Selector::Index),
+        map(
+            preceded(pair(char_('?'), split), cut(parse_logical_or)),
+            |filter| Selector::Filter(Box::new(filter)),
+        ),
+    ))(i)
+}
+
+/// `[selector, selector, ...]`
+fn parse_bracketed<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Vec<Selector>, E> {
+    preceded(
+        char_('['),
+        cut(terminated(
+            separated_list1(
+                preceded(split, char_(',')),
+                cut(preceded(split, context("a selector", parse_selector))),
+            ),
+            preceded(split, context("`,` or `]`", char_(']'))),
+        )),
+    )(i)
+}
+
+fn parse_shorthand<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Selector, E> {
+    alt((
+        value(Selector::Wildcard, char_('*')),
+        map(parse_member_name, |name| Selector::Name(name.to_string())),
+    ))(i)
+}
+
+fn parse_segment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Segment, E> {
+    preceded(
+        split,
+        alt((
+            preceded(
+                tag(".."),
+                cut(context(
+                    "a member name, `*` or `[`",
+                    map(
+                        alt((parse_bracketed, map(parse_shorthand, |s| vec![s]))),
+                        Segment::Descendant,
+                    ),
+                )),
+            ),
+            preceded(
+                char_('.'),
+                cut(context(
+                    "a member name or `*`",
+                    map(parse_shorthand, |s| Segment::Child(vec![s])),
+                )),
+            ),
+            map(parse_bracketed, Segment::Child),
+        )),
+    )(i)
+}
+
+fn parse_query<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Query, E> {
+    map(
+        pair(
+            alt((value(false, char_('$')), value(true, char_('@')))),
+            many0(parse_segment),
+        ),
+        |(relative, segments)| Query {
+            relative,
+            path: JsonPath { segments },
+        },
+    )(i)
+}
+
+fn parse_literal<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonValue<'static>, E> {
+    alt((
+        map(parse_number, JsonValue::into_owned),
+        map(parse_string_literal, |s| {
+            JsonValue::String(Cow::Owned(s.into_owned()))
+        }),
+        value(JsonValue::Boolean(true), tag("true")),
+        value(JsonValue::Boolean(false), tag("false")),
+        value(JsonValue::Null, tag("null")),
+    ))(i)
+}
+
+/// fail at `i` unless the query is singular, only those can be compared
+fn singular<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+    comparable: Comparable,
+) -> Result<Comparable, nom::Err<E>> {
+    match &comparable {
+        Comparable::Query(query) if !query.is_singular() => Err(nom::Err::Failure(E::add_context(
+            i,
+            "a singular query",
+            E::from_error_kind(i, ErrorKind::Verify),
+        ))),
+        _ => Ok(comparable),
+    }
+}
+
+/// `name(` followed by the arguments and `)`
+fn parse_function_args<'a, O, E: ParseError<&'a str> + ContextError<&'a str>>(
+    name: &'static str,
+    args: impl FnMut(&'a str) -> IResult<&'a str, O, E>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O, E> {
+    preceded(
+        pair(tag(name), char_('(')),
+        cut(terminated(
+            preceded(split, args),
+            preceded(split, context("`)`", char_(')'))),
+        )),
+    )
+}
+
+fn parse_comparable<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Comparable, E> {
+    alt((
+        map(parse_literal, Comparable::Literal),
+        map(
+            parse_function_args("length", |i| {
+                let (rest, arg) = context("a value", parse_comparable)(i)?;
+                Ok((rest, singular(i, arg)?))
+            }),
+            |arg| Comparable::Length(Box::new(arg)),
+        ),
+        map(
+            parse_function_args("count", context("a query", parse_query)),
+            Comparable::Count,
+        ),
+        map(
+            parse_function_args("value", context("a query", parse_query)),
+            Comparable::Value,
+        ),
+        map(parse_query, Comparable::Query),
+    ))(i)
+}
+
+fn parse_compare_op<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, CompareOp, E> {
+    alt((
+        value(CompareOp::Eq, tag("==")),
+        value(CompareOp::Ne, tag("!=")),
+        value(CompareOp::Le, tag("<=")),
+        value(CompareOp::Ge, tag(">=")),
+        value(CompareOp::Lt, tag("<")),
+        value(CompareOp::Gt, tag(">")),
+    ))(i)
+}
+
+/// a non-singular query on the left is still parsed, so `@.a[*]` alone is an existence test
+/// and `@.a[*] == 1` fails with a precise error
+fn parse_comparison<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Filter, E> {
+    let (rest, (left, op)) = pair(parse_comparable, preceded(split, parse_compare_op))(i)?;
+    let left = singular(i, left)?;
+    let (right_start, _) = split(rest)?;
+    let (rest, right) = cut(context("a comparable", parse_comparable))(right_start)?;
+    let right = singular(right_start, right)?;
+    Ok((rest, Filter::Compare(left, op, right)))
+}
+
+/// `!` before a parenthesized expression or an existence test
+fn negate(not: Option<char>, filter: Filter) -> Filter {
+    match not {
+        Some(_) => Filter::Not(Box::new(filter)),
+        None => filter,
+    }
+}
+
+fn parse_basic_expr<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Filter, E> {
+    context(
+        "a filter expression",
+        alt((
+            map(
+                pair(
+                    opt(terminated(char_('!'), split)),
+                    delimited(
+                        char_('('),
+                        cut(preceded(split, parse_logical_or)),
+                        cut(preceded(split, context("`)`", char_(')')))),
+                    ),
+                ),
+                |(not, filter)| negate(not, filter),
+            ),
+            parse_comparison,
+            map(
+                pair(opt(terminated(char_('!'), split)), parse_query),
+                |(not, query)| negate(not, Filter::Exists(query)),
+            ),
+        )),
+    )(i)
+}
+
+fn parse_logical_and<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Filter, E> {
+    map(
+        separated_list1(tuple((split, tag("&&"), split)), cut(parse_basic_expr)),
+        |mut filters| match filters.len() {
+            1 => filters.pop().expect("one filter"),
+            _ => Filter::And(filters),
+        },
+    )(i)
+}
+
+fn parse_logical_or<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Filter, E> {
+    map(
+        separated_list1(tuple((split, tag("||"), split)), cut(parse_logical_and)),
+        |mut filters| match filters.len() {
+            1 => filters.pop().expect("one filter"),
+            _ => Filter::Or(filters),
+        },
+    )(i)
+}
+
+/// `$` followed by the segments, the whole input must be the query
+pub fn parse_json_path<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, JsonPath, E> {
+    map(
+        delimited(
+            context("`$`", char_('$')),
+            many0(parse_segment),
+            context("end of input", eof),
+        ),
+        |segments| JsonPath { segments },
+    )(i)
+}
+
+impl FromStr for JsonPath {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_json_path::<JsonParseError>(s) {
+            Ok((_, path)) => Ok(path),
+            Err(e) => Err(JsonError::from_nom(s, e)),
+        }
+    }
+}
+
+/// both numbers compare by value whatever their representation, `1 == 1.0`
+pub(crate) fn compare_numbers(a: &JsonValue<'_>, b: &JsonValue<'_>) -> Option<Ordering> {
+    enum Number {
+        Int(i128),
+        Float(f64),
+    }
+    fn number(value: &JsonValue<'_>) -> Option<Number> {
+        match value {
+            JsonValue::NumberI64(n) => Some(Number::Int(i128::from(*n))),
+            JsonValue::NumberU64(n) => Some(Number::Int(i128::from(*n))),
+            JsonValue::NumberF64(n) => Some(Number::Float(*n)),
+            JsonValue::Number(n) => n
+                .to_i128()
+                .map(Number::Int)
+                .or_else(|| n.to_f64().map(Number::Float)),
+            _ => None,
+        }
+    }
+    match (number(a)?, number(b)?) {
+        (Number::Int(a), Number::Int(b)) => Some(a.cmp(&b)),
+        (Number::Int(a), Number::Float(b)) => (a as f64).partial_cmp(&b),
+        (Number::Float(a), Number::Int(b)) => a.partial_cmp(&(b as f64)),
+        (Number::Float(a), Number::Float(b)) => a.partial_cmp(&b),
+    }
+}
+
+/// JSON equality: numbers by value whatever their representation, objects whatever the key order
+pub(crate) fn values_equal(a: &JsonValue<'_>, b: &JsonValue<'_>) -> bool {
+    match (a, b) {
+        (JsonValue::Null, JsonValue::Null) => true,
+        (JsonValue::Boolean(a), JsonValue::Boolean(b)) => a == b,
+        (JsonValue::String(a), JsonValue::String(b)) => a == b,
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_equal(a, b))
+        }
+        (JsonValue::Map(a), JsonValue::Map(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, v)| b.get(k).is_some_and(|w| values_equal(v, w)))
+        }
+        _ => compare_numbers(a, b) == Some(Ordering::Equal),
+    }
+}
+
+/// `<` is only true between two numbers or two strings, Nothing is never less than anything
+fn less_than(a: Option<&JsonValue<'_>>, b: Option<&JsonValue<'_>>) -> bool {
+    match (a, b) {
+        (Some(JsonValue::String(a)), Some(JsonValue::String(b))) => a < b,
+        (Some(a), Some(b)) => compare_numbers(a, b) == Some(Ordering::Less),
+        _ => false,
+    }
+}
+
+fn equal(a: Option<&JsonValue<'_>>, b: Option<&JsonValue<'_>>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => values_equal(a, b),
+        _ => false,
+    }
+}
+
+impl Comparable {
+    fn eval<'x, 'a>(
+        &'x self,
+        root: &'x JsonValue<'a>,
+        current: &'x JsonValue<'a>,
+    ) -> Option<Cow<'x, JsonValue<'a>>> {
+        match self {
+            Comparable::Literal(value) => Some(Cow::Borrowed(value)),
+            Comparable::Query(query) | Comparable::Value(query) => {
+                match query.select(root, current)[..] {
+                    [node] => Some(Cow::Borrowed(node)),
+                    _ => None,
+                }
+            }
+            Comparable::Length(arg) => {
+                let len = match arg.eval(root, current)?.as_ref() {
+                    JsonValue::String(s) => s.chars().count(),
+                    JsonValue::Array(array) => array.len(),
+                    JsonValue::Map(map) => map.len(),
+                    _ => return None,
+                };
+                Some(Cow::Owned(JsonValue::NumberU64(len as u64)))
+            }
+            Comparable::Count(query) => Some(Cow::Owned(JsonValue::NumberU64(
+                query.select(root, current).len() as u64,
+            ))),
+        }
+    }
+}
+
+impl Filter {
+    fn test(&self, root: &JsonValue<'_>, current: &JsonValue<'_>) -> bool {
+        match self {
+            Filter::Or(filters) => filters.iter().any(|f| f.test(root, current)),
+            Filter::And(filters) => filters.iter().all(|f| f.test(root, current)),
+            Filter::Not(filter) => !filter.test(root, current),
+            Filter::Exists(query) => !query.select(root, current).is_empty(),
+            Filter::Compare(left, op, right) => {
+                let left = left.eval(root, current);
+                let right = right.eval(root, current);
+                let (a, b) = (left.as_deref(), right.as_deref());
+                match op {
+                    CompareOp::Eq => equal(a, b),
+                    CompareOp::Ne => !equal(a, b),
+                    CompareOp::Lt => less_than(a, b),
+                    CompareOp::Le => less_than(a, b) || equal(a, b),
+                    CompareOp::Gt => less_than(b, a),
+                    CompareOp::Ge => less_than(b, a) || equal(a, b),
+                }
+            }
+        }
+    }
+}
+
+impl Query {
+    fn select<'v, 'a>(
+        &self,
+        root: &'v JsonValue<'a>,
+        current: &'v JsonValue<'a>,
+    ) -> Vec<&'v JsonValue<'a>> {
+        let start = if self.relative { current } else { root };
+        self.path.select(root, start)
+    }
+}
+
+/// the children of an array or an object in document order
+fn children<'v, 'a>(node: &'v JsonValue<'a>) -> Box<dyn Iterator<Item = &'v JsonValue<'a>> + 'v> {
+    match node {
+        JsonValue::Array(array) => Box::new(array.iter()),
+        JsonValue::Map(map) => Box::new(map.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// `node` then all its descendants, depth first in document order
+fn descendants<'v, 'a>(node: &'v JsonValue<'a>, out: &mut Vec<&'v JsonValue<'a>>) {
+    out.push(node);
+    for child in children(node) {
+        descendants(child, out);
+    }
+}
+
+/// RFC 9535 section 2.3.4.2.2, indices of an array of `len` elements selected by a slice
+fn slice_indices(start: Option<i64>, end: Option<i64>, step: Option<i64>, len: i64) -> Vec<i64> {
+    let step = step.unwrap_or(1);
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i);
+            i += step;
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i);
+            i += step;
+        }
+    }
+    indices
+}
+
+impl Selector {
+    fn select<'v, 'a>(
+        &self,
+        root: &'v JsonValue<'a>,
+        node: &'v JsonValue<'a>,
+        out: &mut Vec<&'v JsonValue<'a>>,
+    ) {
+        match (self, node) {
+            (Selector::Name(name), JsonValue::Map(map)) => out.extend(
+                // a map parsed with DuplicateKeyPolicy::KeepAll can repeat the name, select every value
+                map.iter()
+                    .filter(|(key, _)| key == name)
+                    .map(|(_, value)| value),
+            ),
+            (Selector::Wildcard, _) => out.extend(children(node)),
+            (Selector::Index(index), JsonValue::Array(array)) => {
+                let index = if *index < 0 {
+                    array.len() as i64 + index
+                } else {
+                    *index
+                };
+                if let Some(item) = usize::try_from(index).ok().and_then(|i| array.get(i)) {
+                    out.push(item);
+                }
+            }
+            (Selector::Slice { start, end, step }, JsonValue::Array(array)) => out.extend(
+                slice_indices(*start, *end, *step, array.len() as i64)
+                    .into_iter()
+                    .map(|i| &array[i as usize]),
+            ),
+            (Selector::Filter(filter), _) => {
+                out.extend(children(node).filter(|child| filter.test(root, child)))
+            }
+            _ => {}
+        }
+    }
+}
+
+impl JsonPath {
+    /// apply the segments one after another starting from the single node `start`
+    fn select<'v, 'a>(
+        &self,
+        root: &'v JsonValue<'a>,
+        start: &'v JsonValue<'a>,
+    ) -> Vec<&'v JsonValue<'a>> {
+        let mut nodes = vec![start];
+        for segment in &self.segments {
+            let mut selected = Vec::new();
+            for node in nodes {
+                match segment {
+                    Segment::Child(selectors) => {
+                        for selector in selectors {
+                            selector.select(root, node, &mut selected);
+                        }
+                    }
+                    Segment::Descendant(selectors) => {
+                        let mut visited = Vec::new();
+                        descendants(node, &mut visited);
+                        for descendant in visited {
+                            for selector in selectors {
+                                selector.select(root, descendant, &mut selected);
+                            }
+                        }
+                    }
+                }
+            }
+            nodes = selected;
+        }
+        nodes
+    }
+
+    /// every node selected by the query in document order, borrowed from `root`
+    pub fn query<'v, 'a>(&self, root: &'v JsonValue<'a>) -> Vec<&'v JsonValue<'a>> {
+        self.select(root, root)
+    }
+}
+
+impl<'a> JsonValue<'a> {
+    /// run a JSONPath query, e.g. `value.query("$..book[?@.price < 10].title")`
+    pub fn query(&self, path: &str) -> Result<Vec<&JsonValue<'a>>, JsonError> {
+        Ok(path.parse::<JsonPath>()?.query(self))
+    }
+}
+
+#[test]
+fn test_json_path() {
+    // the example document of RFC 9535 section 1.5
+    let doc = JsonValue::from_str(
+        r#"{ "store": {
+            "book": [
+              { "category": "reference", "author": "Nigel Rees",
+                "title": "Sayings of the Century", "price": 8.95 },
+              { "category": "fiction", "author": "Evelyn Waugh",
+                "title": "Sword of Honour", "price": 12.99 },
+              { "category": "fiction", "author": "Herman Melville",
+                "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99 },
+              { "category": "fiction", "author": "J. R. R. Tolkien",
+                "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99 }
+            ],
+            "bicycle": { "color": "red", "price": 399 }
+        } }"#,
+    )
+    .unwrap();
+    let query = |path: &str| {
+        doc.query(path)
+            .unwrap()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_eq!(
+        query("$.store.book[*].author"),
+        r#""Nigel Rees" "Evelyn Waugh" "Herman Melville" "J. R. R. Tolkien""#
+    );
+    assert_eq!(query("$..author"), query("$.store.book[*].author"));
+    assert_eq!(query("$.store..price"), "8.95 12.99 8.99 22.99 399");
+    assert_eq!(query("$..book[2].title"), r#""Moby Dick""#);
+    assert_eq!(query("$..book[-1].title"), r#""The Lord of the Rings""#);
+    assert_eq!(query("$..book[0,1].price"), "8.95 12.99");
+    assert_eq!(query("$..book[:2].price"), "8.95 12.99");
+    assert_eq!(query("$..book[::-2].price"), "22.99 12.99");
+    assert_eq!(query("$..book[?@.isbn].price"), "8.99 22.99");
+    assert_eq!(
+        query("$..book[?@.price<10].title"),
+        r#""Sayings of the Century" "Moby Dick""#
+    );
+    assert_eq!(
+        query(r#"$..book[?@.category == 'fiction' && !(@.price > 20 || @.isbn)]['title']"#),
+        r#""Sword of Honour""#
+    );
+    assert_eq!(query("$.store[?length(@) == 2].color"), r#""red""#);
+    assert_eq!(
+        query("$..book[?count(@.*) == 5].author"),
+        r#""Herman Melville" "J. R. R. Tolkien""#
+    );
+    assert_eq!(query("$..book[?@.price == $.store.bicycle.price]"), "");
+    assert_eq!(query("$.store.bicycle[?@ == 399]"), "399");
+    assert_eq!(query("$..[?@ == 1e0]"), "");
+    assert_eq!(query("$.store.book[1:3].price"), "12.99 8.99");
+    assert_eq!(query("$.missing[0]"), "");
+
+    let error = |path: &str| {
+        let e = path.parse::<JsonPath>().unwrap_err();
+        (e.column(), e.expected().unwrap_or_default().to_string())
+    };
+    assert_eq!(error("store"), (1, "`$`".to_string()));
+    assert_eq!(error("$.store["), (9, "a selector".to_string()));
+    assert_eq!(error("$[1 2]"), (5, "`,` or `]`".to_string()));
+    assert_eq!(error("$[01]"), (4, "`,` or `]`".to_string()));
+    assert_eq!(
+        error("$[?@.a[*] == 1]"),
+        (4, "a singular query".to_string())
+    );
+    assert_eq!(error("$[?(@.a]"), (8, "`)`".to_string()));
+    assert_eq!(error("$.a b"), (4, "end of input".to_string()));
+}
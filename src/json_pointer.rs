@@ -0,0 +1,381 @@
+use crate::JsonValue;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char as char_, none_of},
+    combinator::{all_consuming, value},
+    error::ParseError,
+    multi::{fold_many0, many0},
+    sequence::preceded,
+    IResult,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// RFC 6901 JSON Pointer such as `/a/0/b`, stored as unescaped reference tokens
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct JsonPointer {
+    tokens: Vec<String>,
+}
+
+/// why a pointer can't be parsed or doesn't resolve, `pointer` is the prefix of the pointer up to
+/// the token that failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// the pointer isn't empty and doesn't start with `/`, or has a `~` not followed by `0` or `1`
+    Syntax { offset: usize },
+    /// the object has no member with that key
+    MissingKey { pointer: String },
+    /// an array can only be indexed by a decimal number without leading zeros
+    InvalidIndex { pointer: String },
+    /// `-` is past the last element, it is only valid to append
+    IndexOutOfBounds { pointer: String, len: usize },
+    /// a token applied to a value that is neither an object nor an array
+    NotAContainer { pointer: String },
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointerError::Syntax { offset } => {
+                write!(f, "invalid JSON pointer at offset {}", offset)
+            }
+            PointerError::MissingKey { pointer } => write!(f, "`{}`: no such key", pointer),
+            PointerError::InvalidIndex { pointer } => {
+                write!(f, "`{}`: invalid array index", pointer)
+            }
+            PointerError::IndexOutOfBounds { pointer, len } => {
+                write!(
+                    f,
+                    "`{}`: index out of bounds for array of length {}",
+                    pointer, len
+                )
+            }
+            PointerError::NotAContainer { pointer } => {
+                write!(f, "`{}`: parent is neither an object nor an array", pointer)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+/// `~1` is `/` and `~0` is `~`, any other `~` is invalid
+fn parse_reference_token<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, String, E> {
+    fold_many0(
+        alt((value('~', tag("~0")), value('/', tag("~1")), none_of("~/"))),
+        String::new,
+        |mut token, c| {
+            token.push(c);
+            token
+        },
+    )(i)
+}
+
+pub fn parse_json_pointer<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Vec<String>, E> {
+    many0(preceded(char_('/'), parse_reference_token))(i)
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match all_consuming(parse_json_pointer::<nom::error::Error<&str>>)(s) {
+            Ok((_, tokens)) => Ok(Self { tokens }),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(PointerError::Syntax {
+                offset: s.len() - e.input.len(),
+            }),
+            Err(nom::Err::Incomplete(_)) => unreachable!("complete parsers"),
+        }
+    }
+}
+
+/// the escaped form, `"".parse::<JsonPointer>()` and back give the same text
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl JsonPointer {
+    /// the empty pointer that refer to the whole document
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// append an unescaped token, e.g. a key containing `/`
+    pub fn push(&mut self, token: impl Into<String>) {
+        self.tokens.push(token.into());
+    }
+
+    pub fn pop(&mut self) -> Option<String> {
+        self.tokens.pop()
+    }
+
+    /// the escaped pointer up to and including `tokens[depth]`
+    pub(crate) fn prefix(&self, depth: usize) -> String {
+        Self {
+            tokens: self.tokens[..=depth].to_vec(),
+        }
+        .to_string()
+    }
+
+    /// array index of `tokens[depth]`, `-` is `len` when `allow_end` otherwise out of bounds
+    pub(crate) fn index(
+        &self,
+        depth: usize,
+        len: usize,
+        allow_end: bool,
+    ) -> Result<usize, PointerError> {
+        let token = self.tokens[depth].as_str();
+        let out_of_bounds = || PointerError::IndexOutOfBounds {
+            pointer: self.prefix(depth),
+            len,
+        };
+        let index = match token {
+            "-" => len,
+            "0" => 0,
+            _ if token.is_empty()
+                || token.starts_with('0')
+                || !token.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                return Err(PointerError::InvalidIndex {
+                    pointer: self.prefix(depth),
+                })
+            }
+            // only digits are left, so parsing fails only for a number too large for usize
+            _ => token.parse().map_err(|_| out_of_bounds())?,
+        };
+        if index < len || (allow_end && index == len) {
+            Ok(index)
+        } else {
+            Err(out_of_bounds())
+        }
+    }
+
+    fn step<'v, 'a>(
+        &self,
+        depth: usize,
+        value: &'v JsonValue<'a>,
+    ) -> Result<&'v JsonValue<'a>, PointerError> {
+        match value {
+            JsonValue::Map(map) => {
+                map.get(&self.tokens[depth])
+                    .ok_or_else(|| PointerError::MissingKey {
+                        pointer: self.prefix(depth),
+                    })
+            }
+            JsonValue::Array(array) => Ok(&array[self.index(depth, array.len(), false)?]),
+            _ => Err(PointerError::NotAContainer {
+                pointer: self.prefix(depth),
+            }),
+        }
+    }
+
+    fn step_mut<'v, 'a>(
+        &self,
+        depth: usize,
+        value: &'v mut JsonValue<'a>,
+    ) -> Result<&'v mut JsonValue<'a>, PointerError> {
+        match value {
+            JsonValue::Map(map) => {
+                map.get_mut(&self.tokens[depth])
+                    .ok_or_else(|| PointerError::MissingKey {
+                        pointer: self.prefix(depth),
+                    })
+            }
+            JsonValue::Array(array) => {
+                let index = self.index(depth, array.len(), false)?;
+                Ok(&mut array[index])
+            }
+            _ => Err(PointerError::NotAContainer {
+                pointer: self.prefix(depth),
+            }),
+        }
+    }
+
+    pub fn resolve<'v, 'a>(
+        &self,
+        value: &'v JsonValue<'a>,
+    ) -> Result<&'v JsonValue<'a>, PointerError> {
+        (0..self.tokens.len()).try_fold(value, |value, depth| self.step(depth, value))
+    }
+
+    pub fn resolve_mut<'v, 'a>(
+        &self,
+        value: &'v mut JsonValue<'a>,
+    ) -> Result<&'v mut JsonValue<'a>, PointerError> {
+        (0..self.tokens.len()).try_fold(value, |value, depth| self.step_mut(depth, value))
+    }
+
+    /// the container holding the target and the depth of the last token, `None` for the root
+    fn parent_mut<'v, 'a>(
+        &self,
+        value: &'v mut JsonValue<'a>,
+    ) -> Result<Option<(&'v mut JsonValue<'a>, usize)>, PointerError> {
+        let Some(last) = self.tokens.len().checked_sub(1) else {
+            return Ok(None);
+        };
+        let parent = (0..last).try_fold(value, |value, depth| self.step_mut(depth, value))?;
+        Ok(Some((parent, last)))
+    }
+
+    /// JSON Patch `add`: set an object member, insert into an array shifting the following
+    /// elements, `-` append to an array, the root pointer replace the whole document.
+    /// Return the replaced value, if any
+    pub fn insert<'a>(
+        &self,
+        root: &mut JsonValue<'a>,
+        new: JsonValue<'a>,
+    ) -> Result<Option<JsonValue<'a>>, PointerError> {
+        let Some((parent, last)) = self.parent_mut(root)? else {
+            return Ok(Some(std::mem::replace(root, new)));
+        };
+        match parent {
+            JsonValue::Map(map) => Ok(map.insert(self.tokens[last].clone(), new)),
+            JsonValue::Array(array) => {
+                let index = self.index(last, array.len(), true)?;
+                array.insert(index, new);
+                Ok(None)
+            }
+            _ => Err(PointerError::NotAContainer {
+                pointer: self.prefix(last),
+            }),
+        }
+    }
+
+    /// remove the target from its parent, the root pointer leave `null` in place of the document
+    pub fn remove<'a>(&self, root: &mut JsonValue<'a>) -> Result<JsonValue<'a>, PointerError> {
+        let Some((parent, last)) = self.parent_mut(root)? else {
+            return Ok(std::mem::replace(root, JsonValue::Null));
+        };
+        match parent {
+            JsonValue::Map(map) => {
+                map.remove(&self.tokens[last])
+                    .ok_or_else(|| PointerError::MissingKey {
+                        pointer: self.prefix(last),
+                    })
+            }
+            JsonValue::Array(array) => {
+                let index = self.index(last, array.len(), false)?;
+                Ok(array.remove(index))
+            }
+            _ => Err(PointerError::NotAContainer {
+                pointer: self.prefix(last),
+            }),
+        }
+    }
+}
+
+impl<'a> JsonValue<'a> {
+    /// look up a value by JSON Pointer, e.g. `value.pointer("/a/0/b")`
+    pub fn pointer(&self, pointer: &str) -> Result<&JsonValue<'a>, PointerError> {
+        pointer.parse::<JsonPointer>()?.resolve(self)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Result<&mut JsonValue<'a>, PointerError> {
+        pointer.parse::<JsonPointer>()?.resolve_mut(self)
+    }
+
+    /// see [`JsonPointer::insert`]
+    pub fn pointer_insert(
+        &mut self,
+        pointer: &str,
+        value: JsonValue<'a>,
+    ) -> Result<Option<JsonValue<'a>>, PointerError> {
+        pointer.parse::<JsonPointer>()?.insert(self, value)
+    }
+
+    /// see [`JsonPointer::remove`]
+    pub fn pointer_remove(&mut self, pointer: &str) -> Result<JsonValue<'a>, PointerError> {
+        pointer.parse::<JsonPointer>()?.remove(self)
+    }
+}
+
+#[test]
+fn test_json_pointer() {
+    // the examples of RFC 6901 section 5
+    let text = r#"{"foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, "e^f": 3, "g|h": 4,
+        "i\\j": 5, "k\"l": 6, " ": 7, "m~n": 8}"#;
+    let mut doc = JsonValue::from_str(text).unwrap();
+    for (pointer, expected) in [
+        ("", text),
+        ("/foo", r#"["bar", "baz"]"#),
+        ("/foo/0", r#""bar""#),
+        ("/", "0"),
+        ("/a~1b", "1"),
+        ("/c%d", "2"),
+        ("/i\\j", "5"),
+        ("/k\"l", "6"),
+        ("/ ", "7"),
+        ("/m~0n", "8"),
+    ] {
+        let value = doc.pointer(pointer).unwrap();
+        assert_eq!(
+            value,
+            &JsonValue::from_str(expected).unwrap(),
+            "{}",
+            pointer
+        );
+        assert_eq!(pointer.parse::<JsonPointer>().unwrap().to_string(), pointer);
+    }
+
+    let error = |pointer: &str| doc.pointer(pointer).unwrap_err().to_string();
+    assert_eq!(error("foo"), "invalid JSON pointer at offset 0");
+    assert_eq!(error("/m~2n"), "invalid JSON pointer at offset 2");
+    assert_eq!(error("/bar/0"), "`/bar`: no such key");
+    assert_eq!(error("/foo/01"), "`/foo/01`: invalid array index");
+    assert_eq!(error("/foo/"), "`/foo/`: invalid array index");
+    assert_eq!(
+        error("/foo/99999999999999999999999"),
+        "`/foo/99999999999999999999999`: index out of bounds for array of length 2"
+    );
+    assert_eq!(
+        error("/foo/-"),
+        "`/foo/-`: index out of bounds for array of length 2"
+    );
+    assert_eq!(
+        error("/a~1b/x"),
+        "`/a~1b/x`: parent is neither an object nor an array"
+    );
+
+    *doc.pointer_mut("/foo/1").unwrap() = JsonValue::Null;
+    assert_eq!(
+        doc.pointer_insert("/foo/-", JsonValue::Boolean(true)),
+        Ok(None)
+    );
+    assert_eq!(
+        doc.pointer_insert("/foo/0", JsonValue::NumberU64(0)),
+        Ok(None)
+    );
+    assert_eq!(
+        doc.pointer_insert("/", JsonValue::NumberU64(9)),
+        Ok(Some(JsonValue::NumberU64(0)))
+    );
+    assert_eq!(
+        doc.pointer("/foo").unwrap().to_string(),
+        r#"[0,"bar",null,true]"#
+    );
+    assert_eq!(
+        doc.pointer_remove("/foo/1"),
+        Ok(JsonValue::String("bar".into()))
+    );
+    assert_eq!(doc.pointer_remove("/m~0n"), Ok(JsonValue::NumberU64(8)));
+    assert!(doc.pointer("/m~0n").is_err());
+    assert_eq!(
+        doc.pointer_insert("/foo/5", JsonValue::Null),
+        Err(PointerError::IndexOutOfBounds {
+            pointer: "/foo/5".to_string(),
+            len: 3
+        })
+    );
+}
@@ -0,0 +1,362 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_map::JsonMap;
+use crate::json_parser::{
+    build_json_document, split_with, Dialect, DuplicateKeyPolicy, JsonBuilder, ParseOptions, Trivia,
+};
+use crate::JsonValue;
+use nom::IResult;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// the error the grammar hands to the recover methods
+type ParseErr<'a> = nom::Err<JsonParseError<'a>>;
+
+/// whitespace, and comments in JSON5 and JSONC. An unclosed comment is left in place to be
+/// skipped as junk
+fn skip_whitespace(i: &str, dialect: Dialect) -> &str {
+    split_with::<()>(dialect)(i).map_or(i, |(rest, _)| rest)
+}
+
+/// chars that can start a value, after an array item they mean the `,` is missing
+fn starts_value(c: char, dialect: Dialect) -> bool {
+    match dialect {
+        Dialect::Json5 => {
+            starts_value(c, Dialect::Json) || matches!(c, '\'' | '+' | '.' | 'I' | 'N')
+        }
+        _ => matches!(c, '{' | '[' | '"' | '-' | '0'..='9' | 't' | 'f' | 'n'),
+    }
+}
+
+/// chars that can start a key, after an object entry they mean the `,` is missing
+fn starts_key(c: char, dialect: Dialect) -> bool {
+    match dialect {
+        Dialect::Json5 => matches!(c, '"' | '\'' | '_' | '$' | '\\') || c.is_alphabetic(),
+        _ => c == '"',
+    }
+}
+
+/// skip a malformed token up to the next `,`, `}` or `]` outside of strings and brackets, the
+/// first char is always skipped so a stray closing bracket can't stall the parser. Strings are
+/// the ones of the dialect, JSON5 also has single-quoted ones
+fn skip_garbage(i: &str, dialect: Dialect) -> &str {
+    let mut depth = 0usize;
+    // the quote of the string being skipped
+    let mut in_string = None;
+    let mut escaped = false;
+    for (pos, c) in i.char_indices() {
+        if let Some(quote) = in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == quote => in_string = None,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = Some('"'),
+            '\'' if dialect == Dialect::Json5 => in_string = Some('\''),
+            '{' | '[' => depth += 1,
+            ',' | '}' | ']' if depth == 0 && pos > 0 => return &i[pos..],
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    ""
+}
+
+/// builds the tree like the strict parser, a syntax error is recorded and the grammar goes on
+struct Recovery<'a> {
+    input: &'a str,
+    options: ParseOptions,
+    /// closing brackets of the containers being parsed, innermost last
+    closers: Vec<char>,
+    errors: Vec<JsonError>,
+}
+
+impl<'a> Recovery<'a> {
+    fn report(&mut self, at: &str, expected: &str) {
+        let offset = self.input.len() - at.len();
+        let error = JsonError::at(self.input, offset, Some(expected.to_string()));
+        self.errors.push(error);
+    }
+
+    /// end of input or a closing bracket that some open container is waiting for, the
+    /// containers inside it end there
+    fn ends_container(&self, c: Option<char>) -> bool {
+        c.is_none_or(|c| self.closers.contains(&c))
+    }
+
+    /// skip the malformed token at `i`, a missing one leaves the separator for the container
+    fn skip_token(&self, i: &'a str) -> &'a str {
+        match i.chars().next() {
+            None | Some(',' | '}' | ']') => i,
+            Some(_) => skip_garbage(i, self.options.dialect),
+        }
+    }
+}
+
+impl<'a> JsonBuilder<'a, JsonParseError<'a>> for Recovery<'a> {
+    type Value = JsonValue<'a>;
+    type Array = Vec<JsonValue<'a>>;
+    /// the entries and the keys seen, to drop the duplicates the Error policy reports
+    type Map = (Vec<(Cow<'a, str>, JsonValue<'a>)>, HashSet<Cow<'a, str>>);
+    /// `None` for a duplicate that is dropped
+    type Key = Option<Cow<'a, str>>;
+
+    fn scalar(&mut self, _start: &'a str, value: JsonValue<'a>, _rest: &'a str) -> Self::Value {
+        value
+    }
+
+    fn start_array(&mut self, _start: &'a str) -> Self::Array {
+        self.closers.push(']');
+        Vec::new()
+    }
+
+    fn item(&mut self, items: &mut Self::Array, value: Self::Value, _trivia: Trivia<'a>) {
+        items.push(value);
+    }
+
+    fn end_array(&mut self, items: Self::Array, _close: &'a str, _rest: &'a str) -> Self::Value {
+        self.closers.pop();
+        JsonValue::Array(items)
+    }
+
+    fn start_map(&mut self, _start: &'a str) -> Self::Map {
+        self.closers.push('}');
+        (Vec::new(), HashSet::new())
+    }
+
+    fn key(
+        &mut self,
+        (_, keys): &mut Self::Map,
+        start: &'a str,
+        key: Cow<'a, str>,
+        _rest: &'a str,
+    ) -> Result<Self::Key, ParseErr<'a>> {
+        if self.options.duplicate_key_policy == DuplicateKeyPolicy::Error
+            && !keys.insert(key.clone())
+        {
+            self.report(start, "a unique key");
+            return Ok(None);
+        }
+        Ok(Some(key))
+    }
+
+    fn member(&mut self, map: &mut Self::Map, key: Self::Key, value: Self::Value, _: Trivia<'a>) {
+        if let Some(key) = key {
+            map.0.push((key, value));
+        }
+    }
+
+    fn end_map(
+        &mut self,
+        (entries, _): Self::Map,
+        _close: &'a str,
+        _rest: &'a str,
+    ) -> Result<Self::Value, ParseErr<'a>> {
+        self.closers.pop();
+        // with the Error policy the duplicates were already dropped
+        let map = JsonMap::from_entries_with(entries, self.options.duplicate_key_policy)
+            .expect("no duplicate key left");
+        Ok(JsonValue::Map(map))
+    }
+
+    fn recover(&mut self, e: ParseErr<'a>) -> Result<(), ParseErr<'a>> {
+        self.errors.push(JsonError::from_nom(self.input, e));
+        Ok(())
+    }
+
+    fn recover_value(
+        &mut self,
+        i: &'a str,
+        e: ParseErr<'a>,
+    ) -> IResult<&'a str, Self::Value, JsonParseError<'a>> {
+        self.recover(e)?;
+        Ok((self.skip_token(i), JsonValue::Null))
+    }
+
+    fn recover_key(&mut self, i: &'a str, e: ParseErr<'a>) -> Result<&'a str, ParseErr<'a>> {
+        self.recover(e)?;
+        Ok(self.skip_token(i))
+    }
+
+    fn recover_colon(
+        &mut self,
+        i: &'a str,
+        e: ParseErr<'a>,
+    ) -> Result<Option<Self::Value>, ParseErr<'a>> {
+        self.recover(e)?;
+        match i.chars().next() {
+            // `{"a"}`, the value is missing too but one error is enough
+            None | Some(',' | '}' | ']') => Ok(Some(JsonValue::Null)),
+            Some(_) => Ok(None),
+        }
+    }
+
+    /// junk before the separator is reported once and skipped
+    fn recover_separator(
+        &mut self,
+        mut i: &'a str,
+        closer: char,
+        e: ParseErr<'a>,
+    ) -> IResult<&'a str, char, JsonParseError<'a>> {
+        self.recover(e)?;
+        loop {
+            match i.chars().next() {
+                c if self.ends_container(c) => return Ok((i, closer)),
+                // the `,` is missing, the next entry starts here
+                Some(c) if closer == '}' && starts_key(c, self.options.dialect) => {
+                    return Ok((i, ','))
+                }
+                Some(c) if closer == ']' && starts_value(c, self.options.dialect) => {
+                    return Ok((i, ','))
+                }
+                _ => {
+                    i = skip_whitespace(skip_garbage(i, self.options.dialect), self.options.dialect)
+                }
+            }
+            if let Some(rest) = i.strip_prefix(',') {
+                return Ok((rest, ','));
+            }
+            if let Some(rest) = i.strip_prefix(closer) {
+                return Ok((rest, closer));
+            }
+        }
+    }
+}
+
+/// Tolerant version of [`JsonValue::from_str`] for editors and linters: instead of stopping at
+/// the first syntax error it reports every error and keeps going.
+///
+/// A missing or malformed value becomes null, a malformed token is skipped up to the next `,`,
+/// `}` or `]`, and a container that isn't closed ends at the closing bracket of an enclosing one.
+/// A document the strict parser accepts gives the same value and no error, otherwise the first
+/// error is usually the one the strict parser would return (a duplicate key is reported as soon
+/// as it is read, the strict parser first finishes the object)
+pub fn parse_recovering(input: &str) -> (JsonValue<'_>, Vec<JsonError>) {
+    parse_recovering_with(input, ParseOptions::default())
+}
+
+pub fn parse_recovering_with(
+    input: &str,
+    options: ParseOptions,
+) -> (JsonValue<'_>, Vec<JsonError>) {
+    let mut recovery = Recovery {
+        input,
+        options,
+        closers: Vec::new(),
+        errors: Vec::new(),
+    };
+    let (rest, (_, value, _)) = build_json_document(input, options, &mut recovery)
+        .expect("every syntax error is recovered");
+    if !rest.is_empty() {
+        recovery.report(rest, "end of input");
+    }
+    (value, recovery.errors)
+}
+
+#[test]
+fn test_parse_recovering() {
+    let recover = |input| {
+        let (value, errors) = parse_recovering(input);
+        let errors = errors
+            .iter()
+            .map(|e| format!("{}:{} {}", e.line(), e.column(), e.expected().unwrap()))
+            .collect::<Vec<_>>();
+        (value.to_string(), errors)
+    };
+    let text = r#"{"a": 1, "b": tru, "c" 3, "d": [1 2, x, ], "e": "ok",}"#;
+    assert_eq!(
+        recover(text),
+        (
+            r#"{"a":1,"b":null,"c":3,"d":[1,2,null],"e":"ok"}"#.to_string(),
+            vec![
+                "1:15 a JSON value".to_string(),
+                "1:24 `:`".to_string(),
+                "1:35 `,` or `]`".to_string(),
+                "1:38 a JSON value".to_string(),
+                "1:41 a JSON value".to_string(),
+                "1:54 a string key".to_string(),
+            ]
+        )
+    );
+    // an object closed by `]` ends there and the enclosing array still gets its `]`
+    assert_eq!(
+        recover(r#"{"a": [1, {"b": 2]}"#),
+        (
+            r#"{"a":[1,{"b":2}]}"#.to_string(),
+            vec!["1:18 `,` or `}`".to_string()]
+        )
+    );
+    assert_eq!(
+        recover("[1, \"x\\q\", 3] ]"),
+        (
+            r#"[1,null,3]"#.to_string(),
+            vec![
+                "1:8 an escape sequence".to_string(),
+                "1:15 end of input".to_string()
+            ]
+        )
+    );
+    assert_eq!(
+        recover(""),
+        ("null".to_string(), vec!["1:1 a JSON value".to_string()])
+    );
+
+    // same value as the strict parser when it succeeds, its error first when it fails
+    for input in [
+        r#"{"a": [1, {"b": null}], "c": "d"}"#,
+        "[1, 2",
+        r#"{"a": 1, "a": 2}"#,
+        "[01]",
+        "{\"a\": \"\u{1}\"}",
+    ] {
+        for policy in [DuplicateKeyPolicy::LastWins, DuplicateKeyPolicy::Error] {
+            let options = ParseOptions {
+                duplicate_key_policy: policy,
+                ..ParseOptions::default()
+            };
+            let (value, errors) = parse_recovering_with(input, options);
+            match JsonValue::from_str_with(input, options) {
+                Ok(strict) => assert_eq!((value, errors), (strict, vec![])),
+                Err(strict) => assert_eq!(errors[0], strict),
+            }
+        }
+    }
+
+    // comments, trailing commas and JSON5 keys and values are read like the strict parser does
+    for (dialect, input) in [
+        (
+            Dialect::Json5,
+            "{a: [+1, 'x', Infinity,], /* c */ \"b\": {},}",
+        ),
+        (Dialect::Json5, "// c\n[.5, 0x1F]"),
+        (Dialect::Json5, "{a: 1 b: 2}"),
+        (Dialect::Json5, "[1,,]"),
+        (Dialect::Jsonc, "{\"a\" /* c */ : [1, // c\n 2,], }"),
+        (Dialect::Jsonc, "{a: 1}"),
+        (Dialect::Jsonc, "[1, /* c"),
+    ] {
+        let options = ParseOptions {
+            dialect,
+            ..ParseOptions::default()
+        };
+        let (value, errors) = parse_recovering_with(input, options);
+        match JsonValue::from_str_with(input, options) {
+            Ok(strict) => assert_eq!((value, errors), (strict, vec![]), "{}", input),
+            Err(strict) => assert_eq!(errors[0], strict, "{}", input),
+        }
+    }
+    let json5 = ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    };
+    let (value, errors) = parse_recovering_with("{a: 1 b: 2, c: x}", json5);
+    assert_eq!(value.to_string(), r#"{"a":1,"b":2,"c":null}"#);
+    assert_eq!(errors.len(), 2);
+    // a single-quoted string inside skipped junk doesn't end it early
+    let (value, errors) = parse_recovering_with("[x'a, ]b', 2, {k: 1 ?'}, ' , j: 3}]", json5);
+    assert_eq!(value.to_string(), r#"[null,2,{"k":1,"j":3}]"#);
+    assert_eq!(errors.len(), 2);
+}
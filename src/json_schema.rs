@@ -0,0 +1,673 @@
+use crate::json_path::{compare_numbers, values_equal};
+use crate::json_pointer::JsonPointer;
+use crate::JsonValue;
+#[cfg(feature = "regex")]
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// keywords whose value is one subschema
+const SCHEMA_KEYWORDS: [&str; 3] = ["items", "not", "additionalProperties"];
+/// keywords whose value is a non-empty array of subschemas
+const SCHEMA_ARRAY_KEYWORDS: [&str; 3] = ["allOf", "anyOf", "oneOf"];
+/// keywords whose value is an object of subschemas
+const SCHEMA_MAP_KEYWORDS: [&str; 2] = ["properties", "$defs"];
+const LIMIT_KEYWORDS: [&str; 4] = ["minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum"];
+const COUNT_KEYWORDS: [&str; 6] = [
+    "minLength",
+    "maxLength",
+    "minItems",
+    "maxItems",
+    "minProperties",
+    "maxProperties",
+];
+const TYPES: [&str; 7] = [
+    "null", "boolean", "object", "array", "number", "string", "integer",
+];
+
+/// the schema document itself is invalid, `pointer` is the offending keyword
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    pointer: JsonPointer,
+    message: String,
+}
+
+impl SchemaError {
+    fn new(pointer: &JsonPointer, message: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.clone(),
+            message: message.into(),
+        }
+    }
+
+    pub fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid schema at {:?}: {}",
+            self.pointer.to_string(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// one violation found by [`JsonSchema::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    instance_path: JsonPointer,
+    schema_path: JsonPointer,
+    message: String,
+}
+
+impl ValidationError {
+    /// location of the invalid value in the validated document
+    pub fn instance_path(&self) -> &JsonPointer {
+        &self.instance_path
+    }
+
+    /// location of the failing keyword in the schema, `$ref` jumps to the referenced subschema
+    pub fn schema_path(&self) -> &JsonPointer {
+        &self.schema_path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.instance_path.to_string(), self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// `#/json/pointer` to a subschema of the same document
+fn resolve_ref<'s>(
+    root: &'s JsonValue<'static>,
+    reference: &str,
+) -> Option<&'s JsonValue<'static>> {
+    let pointer = reference.strip_prefix('#')?.parse::<JsonPointer>().ok()?;
+    pointer.resolve(root).ok()
+}
+
+/// without the `regex` feature no pattern is ever compiled, a schema using `pattern` is rejected
+#[cfg(not(feature = "regex"))]
+enum Regex {}
+
+#[cfg(not(feature = "regex"))]
+impl Regex {
+    fn is_match(&self, _: &str) -> bool {
+        match *self {}
+    }
+}
+
+/// a non-negative integer such as `3` or `3.0`
+fn as_count(value: &JsonValue<'_>) -> Option<u64> {
+    match value {
+        JsonValue::NumberU64(n) => Some(*n),
+        JsonValue::NumberI64(n) => u64::try_from(*n).ok(),
+        JsonValue::NumberF64(n) if n.fract() == 0.0 && *n >= 0.0 => Some(*n as u64),
+        JsonValue::Number(n) => n.to_u128().and_then(|n| u64::try_from(n).ok()),
+        _ => None,
+    }
+}
+
+fn is_number(value: &JsonValue<'_>) -> bool {
+    matches!(
+        value,
+        JsonValue::NumberI64(_)
+            | JsonValue::NumberU64(_)
+            | JsonValue::NumberF64(_)
+            | JsonValue::Number(_)
+    )
+}
+
+/// `integer` is any number without fractional part, `1.0` included
+fn has_type(value: &JsonValue<'_>, name: &str) -> bool {
+    match (name, value) {
+        ("null", JsonValue::Null)
+        | ("boolean", JsonValue::Boolean(_))
+        | ("object", JsonValue::Map(_))
+        | ("array", JsonValue::Array(_))
+        | ("string", JsonValue::String(_)) => true,
+        ("number", _) => is_number(value),
+        ("integer", JsonValue::NumberF64(n)) => n.fract() == 0.0,
+        ("integer", JsonValue::Number(n)) => n.to_i128().is_some(),
+        ("integer", _) => matches!(value, JsonValue::NumberI64(_) | JsonValue::NumberU64(_)),
+        _ => false,
+    }
+}
+
+/// check the keywords this validator knows and compile the patterns, unknown keywords are
+/// annotations and are ignored like the specification asks. A `$ref` target is checked too, it
+/// may live under a keyword this function doesn't walk such as `definitions`. `refs` holds the
+/// targets already checked so a recursive `$ref` is only followed once
+#[cfg_attr(not(feature = "regex"), allow(clippy::only_used_in_recursion))]
+fn check_schema(
+    root: &JsonValue<'static>,
+    schema: &JsonValue<'static>,
+    path: &mut JsonPointer,
+    patterns: &mut HashMap<String, Regex>,
+    refs: &mut HashSet<String>,
+) -> Result<(), SchemaError> {
+    let map = match schema {
+        JsonValue::Boolean(_) => return Ok(()),
+        JsonValue::Map(map) => map,
+        _ => {
+            return Err(SchemaError::new(
+                path,
+                "a schema must be an object or a boolean",
+            ))
+        }
+    };
+    for (keyword, value) in map.iter() {
+        path.push(keyword);
+        match (keyword, value) {
+            ("type", JsonValue::String(name)) if TYPES.contains(&name.as_ref()) => {}
+            ("type", JsonValue::Array(names))
+                if names.iter().all(|name| {
+                    matches!(name, JsonValue::String(name) if TYPES.contains(&name.as_ref()))
+                }) => {}
+            ("type", _) => return Err(SchemaError::new(path, "unknown type")),
+            ("required", JsonValue::Array(keys))
+                if keys.iter().all(|key| matches!(key, JsonValue::String(_))) => {}
+            ("required", _) => return Err(SchemaError::new(path, "must be an array of strings")),
+            ("enum", JsonValue::Array(_)) => {}
+            ("enum", _) => return Err(SchemaError::new(path, "must be an array")),
+            #[cfg(feature = "regex")]
+            ("pattern", JsonValue::String(pattern)) => match Regex::new(pattern) {
+                Ok(regex) => {
+                    patterns.insert(pattern.to_string(), regex);
+                }
+                Err(e) => return Err(SchemaError::new(path, e.to_string())),
+            },
+            #[cfg(not(feature = "regex"))]
+            ("pattern", JsonValue::String(_)) => {
+                return Err(SchemaError::new(path, "needs the `regex` cargo feature"))
+            }
+            ("pattern", _) => return Err(SchemaError::new(path, "must be a string")),
+            ("$ref", JsonValue::String(reference))
+                if resolve_ref(root, reference).is_some() && !refs.contains(reference.as_ref()) =>
+            {
+                refs.insert(reference.to_string());
+                let target = resolve_ref(root, reference).expect("resolved by the guard");
+                let mut target_path = reference[1..]
+                    .parse::<JsonPointer>()
+                    .expect("resolved by the guard");
+                check_schema(root, target, &mut target_path, patterns, refs)?;
+            }
+            // already checked
+            ("$ref", JsonValue::String(reference)) if resolve_ref(root, reference).is_some() => {}
+            ("$ref", _) => {
+                return Err(SchemaError::new(
+                    path,
+                    "must be a `#` JSON pointer to a subschema of this document",
+                ))
+            }
+            _ if SCHEMA_KEYWORDS.contains(&keyword) => check_schema(root, value, path, patterns, refs)?,
+            (_, JsonValue::Array(schemas))
+                if SCHEMA_ARRAY_KEYWORDS.contains(&keyword) && !schemas.is_empty() =>
+            {
+                for (i, schema) in schemas.iter().enumerate() {
+                    path.push(i.to_string());
+                    check_schema(root, schema, path, patterns, refs)?;
+                    path.pop();
+                }
+            }
+            _ if SCHEMA_ARRAY_KEYWORDS.contains(&keyword) => {
+                return Err(SchemaError::new(path, "must be a non-empty array of schemas"))
+            }
+            (_, JsonValue::Map(schemas)) if SCHEMA_MAP_KEYWORDS.contains(&keyword) => {
+                for (key, schema) in schemas.iter() {
+                    path.push(key);
+                    check_schema(root, schema, path, patterns, refs)?;
+                    path.pop();
+                }
+            }
+            _ if SCHEMA_MAP_KEYWORDS.contains(&keyword) => {
+                return Err(SchemaError::new(path, "must be an object of schemas"))
+            }
+            _ if LIMIT_KEYWORDS.contains(&keyword) && !is_number(value) => {
+                return Err(SchemaError::new(path, "must be a number"))
+            }
+            _ if COUNT_KEYWORDS.contains(&keyword) && as_count(value).is_none() => {
+                return Err(SchemaError::new(path, "must be a non-negative integer"))
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+/// A JSON Schema validator for a subset of draft 2020-12: `type`, `enum`, `const`, `properties`,
+/// `additionalProperties`, `required`, `items`, the min/max keywords, `pattern`, `$ref` to a `#`
+/// pointer in the same document, `$defs`, `allOf`, `anyOf`, `oneOf` and `not`.
+///
+/// `pattern` uses the regex crate syntax which covers the common ECMA-262 patterns, it needs the
+/// `regex` cargo feature and [`JsonSchema::new`] rejects a schema using it without
+pub struct JsonSchema {
+    schema: JsonValue<'static>,
+    patterns: HashMap<String, Regex>,
+}
+
+impl JsonSchema {
+    pub fn new(schema: &JsonValue<'_>) -> Result<Self, SchemaError> {
+        let schema = schema.clone().into_owned();
+        let mut patterns = HashMap::new();
+        check_schema(
+            &schema,
+            &schema,
+            &mut JsonPointer::root(),
+            &mut patterns,
+            &mut HashSet::new(),
+        )?;
+        Ok(Self { schema, patterns })
+    }
+
+    /// every violation in document order, an instance is valid when the list is empty
+    pub fn validate(&self, instance: &JsonValue<'_>) -> Result<(), Vec<ValidationError>> {
+        let mut validator = Validator {
+            schema: self,
+            active_refs: Vec::new(),
+        };
+        let mut errors = Vec::new();
+        validator.validate(
+            &self.schema,
+            &mut JsonPointer::root(),
+            instance,
+            &mut JsonPointer::root(),
+            &mut errors,
+        );
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    pub fn is_valid(&self, instance: &JsonValue<'_>) -> bool {
+        self.validate(instance).is_ok()
+    }
+}
+
+struct Validator<'s> {
+    schema: &'s JsonSchema,
+    /// `$ref` target and instance location pairs being expanded, a `$ref` that comes back to
+    /// the same pair without going deeper into the instance would loop forever
+    active_refs: Vec<(String, String)>,
+}
+
+impl<'s> Validator<'s> {
+    /// errors of `schema` alone, for the applicators that combine the result of subschemas
+    fn errors_of(
+        &mut self,
+        schema: &'s JsonValue<'static>,
+        schema_path: &mut JsonPointer,
+        instance: &JsonValue<'_>,
+        instance_path: &mut JsonPointer,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        self.validate(schema, schema_path, instance, instance_path, &mut errors);
+        errors
+    }
+
+    fn validate(
+        &mut self,
+        schema: &'s JsonValue<'static>,
+        schema_path: &mut JsonPointer,
+        instance: &JsonValue<'_>,
+        instance_path: &mut JsonPointer,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let map = match schema {
+            JsonValue::Map(map) => map,
+            JsonValue::Boolean(true) => return,
+            _ => {
+                errors.push(ValidationError {
+                    instance_path: instance_path.clone(),
+                    schema_path: schema_path.clone(),
+                    message: "no value is allowed here".to_string(),
+                });
+                return;
+            }
+        };
+        for (keyword, value) in map.iter() {
+            schema_path.push(keyword);
+            let mut fail = |message: String| {
+                errors.push(ValidationError {
+                    instance_path: instance_path.clone(),
+                    schema_path: schema_path.clone(),
+                    message,
+                })
+            };
+            match (keyword, value, instance) {
+                ("type", JsonValue::String(name), _) if !has_type(instance, name) => {
+                    fail(format!("must be of type {}", name))
+                }
+                ("type", JsonValue::Array(names), _)
+                    if !names.iter().any(
+                        |name| matches!(name, JsonValue::String(name) if has_type(instance, name)),
+                    ) =>
+                {
+                    let names = names.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    fail(format!("must be of type {}", names.join(" or ")))
+                }
+                ("enum", JsonValue::Array(values), _)
+                    if !values.iter().any(|v| values_equal(v, instance)) =>
+                {
+                    fail(format!("must be one of {}", value))
+                }
+                ("const", _, _) if !values_equal(value, instance) => {
+                    fail(format!("must be equal to {}", value))
+                }
+                (_, _, _) if LIMIT_KEYWORDS.contains(&keyword) && is_number(instance) => {
+                    let ordering = compare_numbers(instance, value);
+                    let (valid, op) = match keyword {
+                        "minimum" => (ordering != Some(Ordering::Less), ">="),
+                        "maximum" => (ordering != Some(Ordering::Greater), "<="),
+                        "exclusiveMinimum" => (ordering == Some(Ordering::Greater), ">"),
+                        _ => (ordering == Some(Ordering::Less), "<"),
+                    };
+                    if !valid {
+                        fail(format!("must be {} {}", op, value))
+                    }
+                }
+                (_, _, _) if COUNT_KEYWORDS.contains(&keyword) => {
+                    // checked by JsonSchema::new, a bad limit is ignored like an unknown keyword
+                    let Some(limit) = as_count(value) else {
+                        schema_path.pop();
+                        continue;
+                    };
+                    let (len, what) = match instance {
+                        JsonValue::String(s) if keyword.ends_with("Length") => {
+                            (s.chars().count(), "characters")
+                        }
+                        JsonValue::Array(array) if keyword.ends_with("Items") => {
+                            (array.len(), "items")
+                        }
+                        JsonValue::Map(map) if keyword.ends_with("Properties") => {
+                            (map.len(), "properties")
+                        }
+                        _ => {
+                            schema_path.pop();
+                            continue;
+                        }
+                    };
+                    let len = len as u64;
+                    if keyword.starts_with("min") && len < limit {
+                        fail(format!("must have at least {} {}", limit, what))
+                    } else if keyword.starts_with("max") && len > limit {
+                        fail(format!("must have at most {} {}", limit, what))
+                    }
+                }
+                ("pattern", JsonValue::String(pattern), JsonValue::String(s))
+                    if self
+                        .schema
+                        .patterns
+                        .get(pattern.as_ref())
+                        .is_some_and(|regex| !regex.is_match(s)) =>
+                {
+                    fail(format!("must match the pattern {:?}", pattern))
+                }
+                ("required", JsonValue::Array(keys), JsonValue::Map(object)) => {
+                    for key in keys {
+                        if let JsonValue::String(key) = key {
+                            if !object.contains_key(key) {
+                                fail(format!("missing required property {:?}", key))
+                            }
+                        }
+                    }
+                }
+                ("properties", JsonValue::Map(properties), JsonValue::Map(object)) => {
+                    for (key, schema) in properties.iter() {
+                        if let Some(value) = object.get(key) {
+                            schema_path.push(key);
+                            instance_path.push(key);
+                            self.validate(schema, schema_path, value, instance_path, errors);
+                            instance_path.pop();
+                            schema_path.pop();
+                        }
+                    }
+                }
+                ("additionalProperties", _, JsonValue::Map(object)) => {
+                    let properties = match map.get("properties") {
+                        Some(JsonValue::Map(properties)) => Some(properties),
+                        _ => None,
+                    };
+                    for (key, item) in object.iter() {
+                        if properties.is_some_and(|properties| properties.contains_key(key)) {
+                            continue;
+                        }
+                        instance_path.push(key);
+                        self.validate(value, schema_path, item, instance_path, errors);
+                        instance_path.pop();
+                    }
+                }
+                ("items", _, JsonValue::Array(items)) => {
+                    for (i, item) in items.iter().enumerate() {
+                        instance_path.push(i.to_string());
+                        self.validate(value, schema_path, item, instance_path, errors);
+                        instance_path.pop();
+                    }
+                }
+                ("allOf" | "anyOf" | "oneOf", JsonValue::Array(schemas), _) => {
+                    let mut failures = Vec::new();
+                    for (i, schema) in schemas.iter().enumerate() {
+                        schema_path.push(i.to_string());
+                        failures.push(self.errors_of(schema, schema_path, instance, instance_path));
+                        schema_path.pop();
+                    }
+                    let matched = failures.iter().filter(|errors| errors.is_empty()).count();
+                    match keyword {
+                        "allOf" => errors.extend(failures.into_iter().flatten()),
+                        "anyOf" if matched == 0 => errors.push(ValidationError {
+                            instance_path: instance_path.clone(),
+                            schema_path: schema_path.clone(),
+                            message: "must match at least one schema of anyOf".to_string(),
+                        }),
+                        "oneOf" if matched != 1 => errors.push(ValidationError {
+                            instance_path: instance_path.clone(),
+                            schema_path: schema_path.clone(),
+                            message: format!(
+                                "must match exactly one schema of oneOf, matched {}",
+                                matched
+                            ),
+                        }),
+                        _ => {}
+                    }
+                }
+                ("not", _, _)
+                    if self
+                        .errors_of(value, schema_path, instance, instance_path)
+                        .is_empty() =>
+                {
+                    errors.push(ValidationError {
+                        instance_path: instance_path.clone(),
+                        schema_path: schema_path.clone(),
+                        message: "must not match the schema of not".to_string(),
+                    })
+                }
+                ("$ref", JsonValue::String(reference), _) => {
+                    let active = (reference.to_string(), instance_path.to_string());
+                    // resolved by JsonSchema::new, a bad reference is ignored rather than panic
+                    let target = resolve_ref(&self.schema.schema, reference);
+                    let target_path = reference.get(1..).map(str::parse::<JsonPointer>);
+                    if let (Some(target), Some(Ok(mut target_path))) = (target, target_path) {
+                        if self.active_refs.contains(&active) {
+                            schema_path.pop();
+                            continue;
+                        }
+                        self.active_refs.push(active);
+                        self.validate(target, &mut target_path, instance, instance_path, errors);
+                        self.active_refs.pop();
+                    }
+                }
+                _ => {}
+            }
+            schema_path.pop();
+        }
+    }
+}
+
+#[test]
+fn test_json_schema() {
+    let schema = JsonValue::from_str(
+        r##"{
+            "$defs": {
+                "positive": {"type": "integer", "exclusiveMinimum": 0},
+                "node": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": {"type": "string", "minLength": 1, "pattern": "^[a-z]+$"},
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/node"}}
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "type": "object",
+            "required": ["id", "root", "kind"],
+            "properties": {
+                "id": {"$ref": "#/$defs/positive"},
+                "root": {"$ref": "#/$defs/node"},
+                "kind": {"enum": ["a", "b"]},
+                "tags": {"type": "array", "maxItems": 2, "items": {"type": ["string", "null"]}},
+                "version": {"const": 2},
+                "size": {"anyOf": [{"type": "string"}, {"minimum": 10, "maximum": 20}]},
+                "mode": {"oneOf": [{"type": "number"}, {"type": "integer"}]},
+                "other": {"not": {"type": "null"}, "allOf": [{"minLength": 2}, {"maxLength": 3}]}
+            }
+        }"##,
+    )
+    .unwrap();
+    let mut schema = schema;
+    if !cfg!(feature = "regex") {
+        assert_eq!(
+            JsonSchema::new(&schema).err().unwrap().message(),
+            "needs the `regex` cargo feature"
+        );
+        schema
+            .pointer_remove("/$defs/node/properties/name/pattern")
+            .unwrap();
+    }
+    let schema = JsonSchema::new(&schema).unwrap();
+
+    let valid = JsonValue::from_str(
+        r#"{"id": 3.0, "kind": "a", "tags": ["x", null], "version": 2.0, "size": 15, "mode": 1.5,
+            "root": {"name": "a", "children": [{"name": "b", "children": []}]}}"#,
+    )
+    .unwrap();
+    assert_eq!(schema.validate(&valid), Ok(()));
+
+    let invalid = JsonValue::from_str(
+        r#"{"id": -1, "tags": [1, "x", "y"], "version": 3, "size": 5, "mode": 1, "other": null,
+            "root": {"name": "", "children": [{"x": 1}]}}"#,
+    )
+    .unwrap();
+    let errors = schema
+        .validate(&invalid)
+        .unwrap_err()
+        .iter()
+        .map(|e| format!("{} {}", e.instance_path(), e.schema_path()))
+        .collect::<Vec<_>>();
+    let pattern_error =
+        cfg!(feature = "regex").then_some("/root/name /$defs/node/properties/name/pattern");
+    assert_eq!(
+        errors.iter().map(String::as_str).collect::<Vec<_>>(),
+        [
+            " /required",
+            "/id /$defs/positive/exclusiveMinimum",
+            "/root/name /$defs/node/properties/name/minLength",
+        ]
+        .into_iter()
+        .chain(pattern_error)
+        .chain([
+            "/root/children/0 /$defs/node/required",
+            "/root/children/0/x /$defs/node/additionalProperties",
+            "/tags /properties/tags/maxItems",
+            "/tags/0 /properties/tags/items/type",
+            "/version /properties/version/const",
+            "/size /properties/size/anyOf",
+            "/mode /properties/mode/oneOf",
+            "/other /properties/other/not",
+        ])
+        .collect::<Vec<_>>()
+    );
+    let e = schema.validate(&invalid).unwrap_err();
+    assert_eq!(e[0].to_string(), r#""": missing required property "kind""#);
+    assert_eq!(
+        e[e.len() - 2].message(),
+        "must match exactly one schema of oneOf, matched 2"
+    );
+
+    // a `$ref` back to the root without consuming the instance must not recurse forever
+    let looping = JsonValue::from_str(r##"{"anyOf": [{"$ref": "#"}, {"type": "null"}]}"##).unwrap();
+    assert!(JsonSchema::new(&looping)
+        .unwrap()
+        .is_valid(&JsonValue::Null));
+
+    // a `$ref` target outside the keywords of this validator is checked and compiled too
+    let outside = JsonValue::from_str(
+        r##"{"definitions": {"x": {"pattern": "^a$"}}, "properties": {"p": {"$ref": "#/definitions/x"}}}"##,
+    )
+    .unwrap();
+    #[cfg(feature = "regex")]
+    {
+        let outside = JsonSchema::new(&outside).unwrap();
+        let errors = outside
+            .validate(&JsonValue::from_str(r#"{"p": "b"}"#).unwrap())
+            .unwrap_err();
+        assert_eq!(
+            errors[0].schema_path().to_string(),
+            "/definitions/x/pattern"
+        );
+        assert!(outside.is_valid(&JsonValue::from_str(r#"{"p": "a"}"#).unwrap()));
+    }
+    #[cfg(not(feature = "regex"))]
+    assert_eq!(
+        JsonSchema::new(&outside)
+            .err()
+            .unwrap()
+            .pointer()
+            .to_string(),
+        "/definitions/x/pattern"
+    );
+
+    for (schema, pointer) in [
+        (r#"{"type": "float"}"#, "/type"),
+        (
+            r#"{"properties": {"a": {"pattern": "("}}}"#,
+            "/properties/a/pattern",
+        ),
+        (r##"{"items": {"$ref": "#/$defs/missing"}}"##, "/items/$ref"),
+        (r#"{"anyOf": []}"#, "/anyOf"),
+        (
+            r##"{"definitions": {"x": {"pattern": "("}}, "$ref": "#/definitions/x"}"##,
+            "/definitions/x/pattern",
+        ),
+        (
+            r##"{"definitions": {"x": {"maxItems": "2"}}, "items": {"$ref": "#/definitions/x"}}"##,
+            "/definitions/x/maxItems",
+        ),
+        (r#"{"minLength": -1}"#, "/minLength"),
+        (r#"[]"#, ""),
+    ] {
+        let schema = JsonValue::from_str(schema).unwrap();
+        let e = JsonSchema::new(&schema).err().unwrap();
+        assert_eq!(e.pointer().to_string(), pointer);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::json_error::JsonError;
+use crate::json_parser::ParseOptions;
+use crate::JsonValue;
+
+/// RFC 7464 record separator that starts every record of a JSON text sequence
+const RS: char = '\u{1e}';
+
+const WHITESPACE: [char; 4] = [' ', '\t', '\r', '\n'];
+
+/// how the documents of a multi-document input are delimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// documents follow each other directly or separated by whitespace, e.g. `{}{}[] 1 2`
+    Concatenated,
+    /// RFC 7464 JSON text sequence, every document is `RS` + JSON text + `\n`
+    Rfc7464,
+}
+
+/// Iterator over the documents of a multi-document input.
+///
+/// With [`Framing::Rfc7464`] a record that fails to parse is reported and the iterator resumes at
+/// the next `RS`, so a truncated record doesn't lose the following ones. Concatenated documents
+/// have no such boundary, iteration ends after the first error
+pub struct JsonSequence<'a> {
+    input: &'a str,
+    rest: &'a str,
+    framing: Framing,
+    options: ParseOptions,
+}
+
+impl<'a> JsonSequence<'a> {
+    /// the framing is [`Framing::Rfc7464`] if the input start with `RS`
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParseOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParseOptions) -> Self {
+        let framing = match input.trim_start_matches(WHITESPACE).starts_with(RS) {
+            true => Framing::Rfc7464,
+            false => Framing::Concatenated,
+        };
+        Self::with_framing(input, framing, options)
+    }
+
+    pub fn with_framing(input: &'a str, framing: Framing, options: ParseOptions) -> Self {
+        Self {
+            input,
+            rest: input,
+            framing,
+            options,
+        }
+    }
+
+    fn offset(&self, s: &str) -> usize {
+        self.input.len() - s.len()
+    }
+
+    fn next_concatenated(&mut self) -> Option<Result<JsonValue<'a>, JsonError>> {
+        if self.rest.trim_start_matches(WHITESPACE).is_empty() {
+            return None;
+        }
+        match JsonValue::parse_prefix_with(self.rest, self.options) {
+            Ok((value, rest)) => {
+                self.rest = rest;
+                Some(Ok(value))
+            }
+            Err(e) => {
+                let offset = self.offset(self.rest);
+                self.rest = "";
+                Some(Err(self.relocate(offset, e)))
+            }
+        }
+    }
+
+    fn next_record(&mut self) -> Option<Result<JsonValue<'a>, JsonError>> {
+        while !self.rest.is_empty() {
+            let start = self.offset(self.rest);
+            let record = self.rest.strip_prefix(RS);
+            let body = record.unwrap_or(self.rest);
+            let (text, rest) = body.split_at(body.find(RS).unwrap_or(body.len()));
+            self.rest = rest;
+            let trimmed = text.trim_start_matches(WHITESPACE);
+            // consecutive separators make empty records, they are skipped
+            if trimmed.is_empty() {
+                continue;
+            }
+            return Some(match record {
+                Some(_) => self.parse_record(start + RS.len_utf8(), text),
+                None => Err(JsonError::at(
+                    self.input,
+                    start + text.len() - trimmed.len(),
+                    Some("a record separator".to_string()),
+                )),
+            });
+        }
+        None
+    }
+
+    /// a top-level number or literal cut at the end of a record still parse, RFC 7464 detects
+    /// the truncation by requiring whitespace after them
+    fn parse_record(&self, offset: usize, text: &'a str) -> Result<JsonValue<'a>, JsonError> {
+        let value =
+            JsonValue::from_str_with(text, self.options).map_err(|e| self.relocate(offset, e))?;
+        let delimited = matches!(
+            value,
+            JsonValue::Map(_) | JsonValue::Array(_) | JsonValue::String(_)
+        );
+        if !delimited && !text.ends_with(WHITESPACE) {
+            return Err(JsonError::at(
+                self.input,
+                offset + text.len(),
+                Some("whitespace after a top-level number or literal".to_string()),
+            ));
+        }
+        Ok(value)
+    }
+
+    /// rebuild an error computed on the text starting at `offset` against the whole input
+    fn relocate(&self, offset: usize, e: JsonError) -> JsonError {
+        JsonError::at(
+            self.input,
+            offset + e.offset(),
+            e.expected().map(String::from),
+        )
+    }
+}
+
+impl<'a> Iterator for JsonSequence<'a> {
+    type Item = Result<JsonValue<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.framing {
+            Framing::Concatenated => self.next_concatenated(),
+            Framing::Rfc7464 => self.next_record(),
+        }
+    }
+}
+
+#[test]
+fn test_json_sequence() {
+    let collect = |sequence: JsonSequence| {
+        sequence
+            .map(|r| match r {
+                Ok(value) => value.to_string(),
+                Err(e) => format!("{}:{} {}", e.line(), e.column(), e.expected().unwrap()),
+            })
+            .collect::<Vec<_>>()
+    };
+    let values = |input| collect(JsonSequence::new(input));
+    assert_eq!(
+        values("{}{\"a\":1}[] 1 \"x\"true\n"),
+        ["{}", r#"{"a":1}"#, "[]", "1", r#""x""#, "true"]
+    );
+    assert_eq!(values("[1] [2"), ["[1]", "1:7 `,` or `]`"]);
+    assert_eq!(values(""), Vec::<String>::new());
+
+    // a truncated record is reported and the next record still parse
+    let input = "\u{1e}{\"a\": 1}\n\u{1e}{\"b\": [\u{1e}\u{1e}\n\u{1e}12\u{1e}\"s\"\n\u{1e}12\n";
+    assert_eq!(
+        values(input),
+        [
+            r#"{"a":1}"#,
+            "2:9 a JSON value",
+            "3:4 whitespace after a top-level number or literal",
+            r#""s""#,
+            "12",
+        ]
+    );
+    let sequence =
+        |input, framing| JsonSequence::with_framing(input, framing, ParseOptions::default());
+    assert_eq!(
+        collect(sequence("x\u{1e}1\n", Framing::Rfc7464)),
+        ["1:1 a record separator", "1"]
+    );
+    assert_eq!(
+        collect(sequence("\u{1e}1\n", Framing::Concatenated)),
+        ["1:1 a JSON value"]
+    );
+}
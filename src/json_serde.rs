@@ -0,0 +1,1248 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_map::JsonMap;
+use crate::json_parser::{
+    parse_key_with, parse_scalar_with, split_with, Dialect, DuplicateKeyPolicy, ParseOptions,
+    RootMode,
+};
+use crate::raw_number::RawNumber;
+use crate::JsonValue;
+use nom::{
+    branch::alt,
+    character::complete::char as char_,
+    combinator::{cut, eof},
+    error::context,
+    IResult,
+};
+use serde::de::{
+    self, value::MapDeserializer, value::SeqDeserializer, DeserializeSeed, EnumAccess,
+    IntoDeserializer, MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use serde::ser::{self, Serialize};
+use serde::{forward_to_deserialize_any, Deserialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+
+/// error of the serde bridge
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerdeError {
+    /// the text isn't a valid document, same error as [`JsonValue::from_str_with`]
+    Syntax(JsonError),
+    /// raised by a `Serialize` or `Deserialize` impl, e.g. a missing field or a wrong type
+    Custom(String),
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerdeError::Syntax(e) => e.fmt(f),
+            SerdeError::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError::Custom(msg.to_string())
+    }
+}
+
+impl ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError::Custom(msg.to_string())
+    }
+}
+
+/// an integer as the parser would store it: u64 when it isn't negative, then i64, and a
+/// [`RawNumber`] beyond 64 bits
+fn integer_value(n: i128) -> JsonValue<'static> {
+    match (u64::try_from(n), i64::try_from(n)) {
+        (Ok(n), _) => JsonValue::NumberU64(n),
+        (_, Ok(n)) => JsonValue::NumberI64(n),
+        _ => JsonValue::Number(RawNumber(n.to_string())),
+    }
+}
+
+fn unsigned_value(n: u128) -> JsonValue<'static> {
+    match i128::try_from(n) {
+        Ok(n) => integer_value(n),
+        Err(_) => JsonValue::Number(RawNumber(n.to_string())),
+    }
+}
+
+/// exact integers go out as integers, other numbers as the nearest f64
+impl Serialize for RawNumber {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self.to_i128(), self.to_u128()) {
+            (Some(n), _) => match (u64::try_from(n), i64::try_from(n)) {
+                (Ok(n), _) => serializer.serialize_u64(n),
+                (_, Ok(n)) => serializer.serialize_i64(n),
+                _ => serializer.serialize_i128(n),
+            },
+            (None, Some(n)) => serializer.serialize_u128(n),
+            (None, None) => match self.to_f64() {
+                Some(n) => serializer.serialize_f64(n),
+                None => Err(ser::Error::custom(format_args!(
+                    "number `{}` out of f64 range",
+                    self
+                ))),
+            },
+        }
+    }
+}
+
+impl Serialize for JsonValue<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Boolean(b) => serializer.serialize_bool(*b),
+            JsonValue::NumberI64(n) => serializer.serialize_i64(*n),
+            JsonValue::NumberU64(n) => serializer.serialize_u64(*n),
+            JsonValue::NumberF64(n) => serializer.serialize_f64(*n),
+            JsonValue::Number(n) => n.serialize(serializer),
+            JsonValue::String(s) => serializer.serialize_str(s),
+            JsonValue::Array(items) => serializer.collect_seq(items),
+            JsonValue::Map(map) => serializer.collect_map(map.iter()),
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JsonValue<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(JsonValue::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(JsonValue::NumberU64(v))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> {
+        Ok(integer_value(v))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+        Ok(unsigned_value(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(JsonValue::NumberF64(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(JsonValue::String(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(JsonValue::String(Cow::Borrowed(v)))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(JsonValue::String(Cow::Owned(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        JsonValue::deserialize(d)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(JsonValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(key) = map.next_key_seed(KeyVisitor)? {
+            entries.push((key, map.next_value()?));
+        }
+        let map = JsonMap::from_entries_with(entries, DuplicateKeyPolicy::LastWins)
+            .expect("LastWins never reject duplicate");
+        Ok(JsonValue::Map(map))
+    }
+}
+
+/// a map key, borrowed when the deserializer hands out borrowed strings
+struct KeyVisitor;
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Cow<'de, str>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(v.to_string()))
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(Cow::Borrowed(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(v))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for KeyVisitor {
+    type Value = Cow<'de, str>;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        d.deserialize_str(self)
+    }
+}
+
+/// strings without escapes stay borrowed from the deserializer input, duplicate keys are
+/// resolved like [`DuplicateKeyPolicy::LastWins`]
+impl<'de> Deserialize<'de> for JsonValue<'de> {
+    fn deserialize<D: de::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        d.deserialize_any(ValueVisitor)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for JsonValue<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// read a parsed tree into a typed value, see [`from_value`]
+impl<'de> de::Deserializer<'de> for JsonValue<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            JsonValue::Null => visitor.visit_unit(),
+            JsonValue::Boolean(b) => visitor.visit_bool(b),
+            JsonValue::NumberI64(n) => visitor.visit_i64(n),
+            JsonValue::NumberU64(n) => visitor.visit_u64(n),
+            JsonValue::NumberF64(n) => visitor.visit_f64(n),
+            JsonValue::Number(n) => match (n.to_i128(), n.to_u128()) {
+                (Some(i), _) => integer_value(i).deserialize_any(visitor),
+                (None, Some(u)) => visitor.visit_u128(u),
+                (None, None) => match n.to_f64() {
+                    Some(f) => visitor.visit_f64(f),
+                    None => Err(de::Error::invalid_value(
+                        Unexpected::Other(n.as_str()),
+                        &"a number in f64 range",
+                    )),
+                },
+            },
+            JsonValue::String(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            JsonValue::String(Cow::Owned(s)) => visitor.visit_string(s),
+            JsonValue::Array(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            JsonValue::Map(map) => {
+                let entries = map.into_iter().map(|(k, v)| (MapKey(k), v));
+                let mut map = MapDeserializer::new(entries);
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            JsonValue::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// a unit variant is a string, the other variants an object with the variant as only key
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self {
+            JsonValue::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            JsonValue::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("one entry");
+                visitor.visit_enum(ValueEnum { variant, value })
+            }
+            value => Err(de::Error::invalid_type(
+                unexpected(&value),
+                &"a string or an object with one key",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// an object key, a string that also reads as the integer, float or bool `serialize_key` wrote
+/// it from, so `{"1": true}` turns back into a `BTreeMap<u32, bool>`
+struct MapKey<'de>(Cow<'de, str>);
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+            match self.0.parse() {
+                Ok(key) => visitor.$visit(key),
+                Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor)),
+            }
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for MapKey<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            Cow::Borrowed(key) => visitor.visit_borrowed_str(key),
+            Cow::Owned(key) => visitor.visit_string(key),
+        }
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32, deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8, deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32, deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32, deserialize_f64 => visit_f64
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for MapKey<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn unexpected<'a>(value: &'a JsonValue<'_>) -> Unexpected<'a> {
+    match value {
+        JsonValue::Null => Unexpected::Unit,
+        JsonValue::Boolean(b) => Unexpected::Bool(*b),
+        JsonValue::NumberI64(n) => Unexpected::Signed(*n),
+        JsonValue::NumberU64(n) => Unexpected::Unsigned(*n),
+        JsonValue::NumberF64(n) => Unexpected::Float(*n),
+        JsonValue::Number(n) => Unexpected::Other(n.as_str()),
+        JsonValue::String(s) => Unexpected::Str(s),
+        JsonValue::Array(_) => Unexpected::Seq,
+        JsonValue::Map(_) => Unexpected::Map,
+    }
+}
+
+struct ValueEnum<'de> {
+    variant: Cow<'de, str>,
+    value: JsonValue<'de>,
+}
+
+impl<'de> EnumAccess<'de> for ValueEnum<'de> {
+    type Error = SerdeError;
+    type Variant = JsonValue<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), SerdeError> {
+        let variant = seed.deserialize(JsonValue::String(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+/// the value of `{"variant": value}`
+impl<'de> VariantAccess<'de> for JsonValue<'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        <()>::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+/// A serde Deserializer reading the text with the parsers of [`crate::json_parser`] as it goes,
+/// without building a [`JsonValue`] first. Use [`from_str_with`] unless the document is followed
+/// by something else, then call [`Deserializer::end`] only where the document should stop.
+///
+/// Only [`DuplicateKeyPolicy::Error`] is enforced, with the other policies the visitor sees
+/// every entry (a derived struct rejects a repeated field, a `HashMap` keeps the last value)
+pub struct Deserializer<'de> {
+    input: &'de str,
+    rest: &'de str,
+    options: ParseOptions,
+}
+
+type ParseResult<'de, T> = IResult<&'de str, T, JsonParseError<'de>>;
+
+impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'de str) -> Self {
+        Self::from_str_with(input, ParseOptions::default())
+    }
+
+    pub fn from_str_with(input: &'de str, options: ParseOptions) -> Self {
+        Self {
+            input,
+            rest: input,
+            options,
+        }
+    }
+
+    /// fails unless only whitespace, or comments in JSON5 and JSONC, is left
+    pub fn end(&mut self) -> Result<(), SerdeError> {
+        self.parse(context("end of input", eof)).map(drop)
+    }
+
+    /// an error at `at`, a suffix of the input
+    fn error_at(&self, at: &str, expected: &str) -> SerdeError {
+        let offset = self.input.len() - at.len();
+        SerdeError::Syntax(JsonError::at(
+            self.input,
+            offset,
+            Some(expected.to_string()),
+        ))
+    }
+
+    /// skip the whitespace then run `parser`
+    fn parse<T>(
+        &mut self,
+        mut parser: impl FnMut(&'de str) -> ParseResult<'de, T>,
+    ) -> Result<T, SerdeError> {
+        let input = self.input;
+        let syntax = |e| SerdeError::Syntax(JsonError::from_nom(input, e));
+        let (rest, _) = split_with(self.options.dialect)(self.rest).map_err(syntax)?;
+        let (rest, value) = parser(rest).map_err(syntax)?;
+        self.rest = rest;
+        Ok(value)
+    }
+
+    /// the next char after the whitespace, it isn't consumed
+    fn peek(&mut self) -> Result<Option<char>, SerdeError> {
+        self.parse(|i| Ok((i, i.chars().next())))
+    }
+
+    fn parse_scalar(&mut self) -> Result<JsonValue<'de>, SerdeError> {
+        let options = self.options;
+        self.parse(context("a JSON value", parse_scalar_with(options)))
+    }
+
+    fn parse_key(&mut self) -> Result<Cow<'de, str>, SerdeError> {
+        let dialect = self.options.dialect;
+        self.parse(cut(parse_key_with(dialect)))
+    }
+
+    fn parse_colon(&mut self) -> Result<(), SerdeError> {
+        self.parse(cut(context("`:`", char_(':')))).map(drop)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.peek()? {
+            Some(closer @ ('{' | '[')) => {
+                self.rest = &self.rest[1..];
+                let closer = if closer == '{' { '}' } else { ']' };
+                let mut entries = Entries::new(self, closer);
+                let value = if closer == '}' {
+                    visitor.visit_map(&mut entries)?
+                } else {
+                    visitor.visit_seq(&mut entries)?
+                };
+                entries.end()?;
+                Ok(value)
+            }
+            _ => self.parse_scalar()?.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        if self.peek()? == Some('n') {
+            self.parse_scalar()?;
+            return visitor.visit_none();
+        }
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        if self.peek()? != Some('{') {
+            return self
+                .parse_scalar()?
+                .deserialize_enum(name, variants, visitor);
+        }
+        self.rest = &self.rest[1..];
+        let variant = self.parse_key()?;
+        self.parse_colon()?;
+        let value = visitor.visit_enum(TextEnum { de: self, variant })?;
+        let mut entries = Entries::new(self, '}');
+        entries.first = false;
+        entries.end()?;
+        Ok(value)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// the items of an array or the members of an object being read
+struct Entries<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    closer: char,
+    first: bool,
+    done: bool,
+    /// keys seen so far with [`DuplicateKeyPolicy::Error`]
+    keys: HashSet<Cow<'de, str>>,
+}
+
+impl<'a, 'de> Entries<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, closer: char) -> Self {
+        Self {
+            de,
+            closer,
+            first: true,
+            done: false,
+            keys: HashSet::new(),
+        }
+    }
+
+    /// read up to the next entry, `false` when the closing bracket was read instead
+    fn has_next(&mut self) -> Result<bool, SerdeError> {
+        if self.done {
+            return Ok(false);
+        }
+        let closer = self.closer;
+        if !self.first {
+            let expected = if closer == '}' {
+                "`,` or `}`"
+            } else {
+                "`,` or `]`"
+            };
+            let separator = self
+                .de
+                .parse(cut(context(expected, alt((char_(','), char_(closer))))))?;
+            if separator == closer {
+                self.done = true;
+                return Ok(false);
+            }
+        }
+        // `}` or `]` right after the opening bracket, or after a trailing comma in JSON5 and JSONC
+        let allowed = self.first || self.de.options.dialect != Dialect::Json;
+        self.first = false;
+        if allowed && self.de.peek()? == Some(closer) {
+            self.de.rest = &self.de.rest[1..];
+            self.done = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// the visitor may stop early, e.g. a tuple of known length, the rest must be the closer
+    fn end(&mut self) -> Result<(), SerdeError> {
+        if !self.has_next()? {
+            return Ok(());
+        }
+        self.de.peek()?;
+        Err(self
+            .de
+            .error_at(self.de.rest, &format!("`{}`", self.closer)))
+    }
+}
+
+impl<'de> SeqAccess<'de> for Entries<'_, 'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries<'_, 'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+        self.de.peek()?;
+        let key_start = self.de.rest;
+        let key = self.de.parse_key()?;
+        if self.de.options.duplicate_key_policy == DuplicateKeyPolicy::Error
+            && !self.keys.insert(key.clone())
+        {
+            return Err(self.de.error_at(key_start, "a unique key"));
+        }
+        seed.deserialize(MapKey(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.de.parse_colon()?;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct TextEnum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    variant: Cow<'de, str>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for TextEnum<'a, 'de> {
+    type Error = SerdeError;
+    type Variant = &'a mut Deserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), SerdeError> {
+        let variant = seed.deserialize(JsonValue::String(self.variant))?;
+        Ok((variant, self.de))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &mut Deserializer<'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        <()>::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+/// deserialize a typed value from the text, the serde counterpart of [`JsonValue::from_str`]
+pub fn from_str<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T, SerdeError> {
+    from_str_with(s, ParseOptions::default())
+}
+
+pub fn from_str_with<'de, T: Deserialize<'de>>(
+    s: &'de str,
+    options: ParseOptions,
+) -> Result<T, SerdeError> {
+    let mut deserializer = Deserializer::from_str_with(s, options);
+    if options.root_mode == RootMode::Rfc4627 && !matches!(deserializer.peek()?, Some('{' | '[')) {
+        return Err(deserializer.error_at(deserializer.rest, "`{` or `[`"));
+    }
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// deserialize a typed value from a parsed tree, e.g. the output of
+/// [`crate::json_parser::parse_json_str`]
+pub fn from_value<'de, T: Deserialize<'de>>(value: JsonValue<'de>) -> Result<T, SerdeError> {
+    T::deserialize(value)
+}
+
+/// serialize a value into a [`JsonValue`], integers beyond 64 bits become [`RawNumber`]
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<JsonValue<'static>, SerdeError> {
+    value.serialize(ValueSerializer)
+}
+
+/// the serde Serializer behind [`to_value`]. A unit variant is written as a string, the other
+/// variants as an object with the variant as only key
+pub struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeObject;
+    type SerializeStruct = SerializeObject;
+    type SerializeStructVariant = SerializeObject;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, SerdeError> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, SerdeError> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, SerdeError> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, SerdeError> {
+        Ok(integer_value(v.into()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, SerdeError> {
+        Ok(integer_value(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::NumberU64(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::NumberU64(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::NumberU64(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::NumberU64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, SerdeError> {
+        Ok(unsigned_value(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::NumberF64(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::NumberF64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::String(Cow::Owned(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::String(Cow::Owned(v.to_string())))
+    }
+
+    /// an array of numbers
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, SerdeError> {
+        let bytes = v.iter().map(|b| JsonValue::NumberU64((*b).into()));
+        Ok(JsonValue::Array(bytes.collect()))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, SerdeError> {
+        Ok(JsonValue::String(Cow::Borrowed(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, SerdeError> {
+        let mut map = JsonMap::new();
+        map.insert(variant, value.serialize(self)?);
+        Ok(JsonValue::Map(map))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, SerdeError> {
+        Ok(SerializeArray {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArray, SerdeError> {
+        Ok(SerializeArray {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeObject, SerdeError> {
+        Ok(SerializeObject {
+            variant: None,
+            map: JsonMap::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeObject, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeObject, SerdeError> {
+        Ok(SerializeObject {
+            variant: Some(variant),
+            map: JsonMap::new(),
+            key: None,
+        })
+    }
+}
+
+/// `value`, or `{"variant": value}` for an enum variant
+fn wrap_variant(variant: Option<&'static str>, value: JsonValue<'static>) -> JsonValue<'static> {
+    match variant {
+        Some(variant) => {
+            let mut map = JsonMap::new();
+            map.insert(variant, value);
+            JsonValue::Map(map)
+        }
+        None => value,
+    }
+}
+
+pub struct SerializeArray {
+    variant: Option<&'static str>,
+    items: Vec<JsonValue<'static>>,
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.items.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        Ok(wrap_variant(self.variant, JsonValue::Array(self.items)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct SerializeObject {
+    variant: Option<&'static str>,
+    map: JsonMap<'static>,
+    /// the key given to `serialize_key` until its value comes
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeObject {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+
+    /// strings, and like JavaScript numbers and booleans written as strings
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let key = match to_value(key)? {
+            JsonValue::String(key) => key.into_owned(),
+            key @ (JsonValue::Boolean(_)
+            | JsonValue::NumberI64(_)
+            | JsonValue::NumberU64(_)
+            | JsonValue::NumberF64(_)
+            | JsonValue::Number(_)) => key.to_string(),
+            key => {
+                return Err(ser::Error::custom(format_args!(
+                    "a map key must be a string, not {}",
+                    unexpected(&key)
+                )))
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_key before serialize_value");
+        self.map.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        Ok(wrap_variant(self.variant, JsonValue::Map(self.map)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeObject {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.map.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeObject {
+    type Ok = JsonValue<'static>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+#[test]
+fn test_json_serde() {
+    use crate::json_parser::NumberMode;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config<'a> {
+        name: &'a str,
+        port: u16,
+        tags: Vec<String>,
+        shapes: Vec<Shape>,
+        limit: Option<i64>,
+        extra: BTreeMap<String, bool>,
+    }
+
+    let text = r#"{
+        "name": "demo", "port": 8080, "tags": ["a", "b\n"],
+        "shapes": ["Point", {"Circle": 1.5}, {"Rect": {"w": 2, "h": 3}}],
+        "limit": null, "extra": {"x": true}, "ignored": [1, {"y": 2}]
+    }"#;
+    let config = Config {
+        name: "demo",
+        port: 8080,
+        tags: vec!["a".to_string(), "b\n".to_string()],
+        shapes: vec![Shape::Point, Shape::Circle(1.5), Shape::Rect { w: 2, h: 3 }],
+        limit: None,
+        extra: BTreeMap::from([("x".to_string(), true)]),
+    };
+    assert_eq!(from_str::<Config>(text).unwrap(), config);
+    let value = JsonValue::from_str(text).unwrap();
+    assert_eq!(from_value::<Config>(value.clone()).unwrap(), config);
+    assert_eq!(
+        to_value(&config).unwrap().to_string(),
+        concat!(
+            r#"{"name":"demo","port":8080,"tags":["a","b\n"],"#,
+            r#""shapes":["Point",{"Circle":1.5},{"Rect":{"w":2,"h":3}}],"#,
+            r#""limit":null,"extra":{"x":true}}"#
+        )
+    );
+    // JsonValue itself goes both ways, strings without escapes stay borrowed
+    assert_eq!(to_value(&value).unwrap(), value);
+    let borrowed = from_str::<JsonValue>(text).unwrap();
+    assert_eq!(borrowed, value);
+    assert!(matches!(
+        borrowed.pointer("/name"),
+        Ok(JsonValue::String(Cow::Borrowed("demo")))
+    ));
+    let precise = ParseOptions {
+        number_mode: NumberMode::ArbitraryPrecision,
+        ..ParseOptions::default()
+    };
+    let big = JsonValue::from_str_with("[340282366920938463463374607431768211455, -1.5]", precise)
+        .unwrap();
+    assert_eq!(
+        to_value(&big).unwrap().to_string(),
+        "[340282366920938463463374607431768211455,-1.5]"
+    );
+    assert_eq!(from_value::<(u128, f64)>(big).unwrap(), (u128::MAX, -1.5));
+
+    let error = |result: Result<_, SerdeError>| match result {
+        Err(SerdeError::Syntax(e)) => format!("{} {}", e.column(), e.expected().unwrap()),
+        Err(SerdeError::Custom(message)) => message,
+        Ok(()) => unreachable!(),
+    };
+    // integer and bool keys are written as strings and read back from them
+    let flags = BTreeMap::from([(1u32, true), (20, false)]);
+    let value = to_value(&flags).unwrap();
+    assert_eq!(value.to_string(), r#"{"1":true,"20":false}"#);
+    assert_eq!(from_value::<BTreeMap<u32, bool>>(value).unwrap(), flags);
+    assert_eq!(
+        from_str::<BTreeMap<u32, bool>>(r#"{"1": true, "20": false}"#).unwrap(),
+        flags
+    );
+    assert_eq!(
+        from_str::<BTreeMap<bool, i8>>(r#"{"true": -1}"#).unwrap(),
+        BTreeMap::from([(true, -1)])
+    );
+    assert_eq!(
+        error(from_str::<BTreeMap<u8, u8>>(r#"{"x": 1}"#).map(drop)),
+        "invalid value: string \"x\", expected u8"
+    );
+
+    // syntax errors are the ones of the tree parser
+    for input in [r#"{"name": "demo",}"#, "[1, 2] x", "[1 2]", "{\"a\" 1}"] {
+        assert_eq!(
+            from_str::<JsonValue>(input),
+            Err(SerdeError::Syntax(JsonValue::from_str(input).unwrap_err()))
+        );
+    }
+    let strict = ParseOptions {
+        duplicate_key_policy: DuplicateKeyPolicy::Error,
+        root_mode: RootMode::Rfc4627,
+        ..ParseOptions::default()
+    };
+    for input in [r#"{"a": 1, "a": 2}"#, " 1"] {
+        assert_eq!(
+            from_str_with::<BTreeMap<String, u8>>(input, strict),
+            Err(SerdeError::Syntax(
+                JsonValue::from_str_with(input, strict).unwrap_err()
+            ))
+        );
+    }
+    assert_eq!(
+        error(from_str::<u8>("300").map(drop)),
+        "invalid value: integer `300`, expected u8"
+    );
+    assert_eq!(error(from_str::<(u8,)>("[1, 2]").map(drop)), "5 `]`");
+    assert_eq!(
+        error(from_str::<Config>(r#"{"name": "x"}"#).map(drop)),
+        "missing field `port`"
+    );
+
+    let json5 = ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    };
+    assert_eq!(
+        from_str_with::<Vec<f64>>("[+1, .5, 0x10, /* four */ 4e0,]", json5).unwrap(),
+        [1.0, 0.5, 16.0, 4.0]
+    );
+    assert_eq!(
+        from_str_with::<Shape>("{Rect: {w: 1, h: 2,},}", json5).unwrap(),
+        Shape::Rect { w: 1, h: 2 }
+    );
+}
@@ -0,0 +1,274 @@
+use crate::json_comments::{Comment, CommentAnchor};
+use crate::json_pointer::JsonPointer;
+use crate::JsonValue;
+use std::fmt::{self, Write};
+
+/// write `s` as a double quoted JSON string, only `"`, `\` and control characters need escaping
+pub fn write_string<W: Write>(w: &mut W, s: &str) -> fmt::Result {
+    w.write_char('"')?;
+    let mut start = 0;
+    for (pos, c) in s.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{08}' => "\\b",
+            '\u{0C}' => "\\f",
+            c if c < '\u{20}' => "",
+            _ => continue,
+        };
+        w.write_str(&s[start..pos])?;
+        if escaped.is_empty() {
+            write!(w, "\\u{:04x}", c as u32)?;
+        } else {
+            w.write_str(escaped)?;
+        }
+        start = pos + c.len_utf8();
+    }
+    w.write_str(&s[start..])?;
+    w.write_char('"')
+}
+
+/// f64 Debug format is the shortest text that parse back to the same f64 and always has a `.`
+/// or an exponent, e.g. `1.0`, `0.1`, `1e21`, so it stay a float after a round trip.
+/// JSON can't represent NaN and infinity, they are written as `null`
+fn write_f64<W: Write>(w: &mut W, n: f64) -> fmt::Result {
+    if n.is_finite() {
+        write!(w, "{:?}", n)
+    } else {
+        w.write_str("null")
+    }
+}
+
+fn write_indent<W: Write>(w: &mut W, indent: Option<usize>, depth: usize) -> fmt::Result {
+    if let Some(indent) = indent {
+        w.write_char('\n')?;
+        for _ in 0..indent * depth {
+            w.write_char(' ')?;
+        }
+    }
+    Ok(())
+}
+
+/// the comments to write back and the pointer of the value being written
+struct CommentWriter<'c, 'a> {
+    comments: &'c [Comment<'a>],
+    path: JsonPointer,
+}
+
+impl CommentWriter<'_, '_> {
+    fn texts<'s>(
+        &'s self,
+        anchor: impl Fn(&JsonPointer) -> CommentAnchor + 's,
+    ) -> impl Iterator<Item = &'s str> + 's {
+        let anchor = anchor(&self.path);
+        self.comments
+            .iter()
+            .filter(move |c| c.anchor == anchor)
+            .map(|c| c.text.as_ref())
+    }
+
+    /// comment lines before the value, the indent of the value is already written
+    fn write_before<W: Write>(
+        &self,
+        w: &mut W,
+        indent: Option<usize>,
+        depth: usize,
+    ) -> fmt::Result {
+        for text in self.texts(|p| CommentAnchor::Before(p.clone())) {
+            w.write_str(text)?;
+            write_indent(w, indent, depth)?;
+        }
+        Ok(())
+    }
+
+    fn write_after<W: Write>(&self, w: &mut W) -> fmt::Result {
+        for text in self.texts(|p| CommentAnchor::After(p.clone())) {
+            w.write_char(' ')?;
+            w.write_str(text)?;
+        }
+        Ok(())
+    }
+
+    /// comment lines after the last member of the container
+    fn write_end<W: Write>(&self, w: &mut W, indent: Option<usize>, depth: usize) -> fmt::Result {
+        for text in self.texts(|p| CommentAnchor::End(p.clone())) {
+            write_indent(w, indent, depth)?;
+            w.write_str(text)?;
+        }
+        Ok(())
+    }
+}
+
+/// `indent` is `None` for compact output, otherwise the number of spaces per nesting level
+fn write_value<W: Write>(
+    w: &mut W,
+    value: &JsonValue<'_>,
+    indent: Option<usize>,
+    depth: usize,
+    mut comments: Option<&mut CommentWriter<'_, '_>>,
+) -> fmt::Result {
+    let has_end_comments = |comments: &Option<&mut CommentWriter<'_, '_>>| {
+        comments
+            .as_ref()
+            .is_some_and(|c| c.texts(|p| CommentAnchor::End(p.clone())).next().is_some())
+    };
+    match value {
+        JsonValue::Null => w.write_str("null"),
+        JsonValue::Boolean(b) => write!(w, "{}", b),
+        JsonValue::NumberI64(n) => write!(w, "{}", n),
+        JsonValue::NumberU64(n) => write!(w, "{}", n),
+        JsonValue::NumberF64(n) => write_f64(w, *n),
+        JsonValue::Number(n) => w.write_str(n.as_str()),
+        JsonValue::String(s) => write_string(w, s),
+        JsonValue::Array(array) => {
+            if array.is_empty() && !has_end_comments(&comments) {
+                return w.write_str("[]");
+            }
+            w.write_char('[')?;
+            for (i, item) in array.iter().enumerate() {
+                write_indent(w, indent, depth + 1)?;
+                if let Some(c) = comments.as_deref_mut() {
+                    c.path.push(i.to_string());
+                    c.write_before(w, indent, depth + 1)?;
+                }
+                write_value(w, item, indent, depth + 1, comments.as_deref_mut())?;
+                if i + 1 < array.len() {
+                    w.write_char(',')?;
+                }
+                if let Some(c) = comments.as_deref_mut() {
+                    c.write_after(w)?;
+                    c.path.pop();
+                }
+            }
+            if let Some(c) = comments {
+                c.write_end(w, indent, depth + 1)?;
+            }
+            write_indent(w, indent, depth)?;
+            w.write_char(']')
+        }
+        JsonValue::Map(map) => {
+            if map.is_empty() && !has_end_comments(&comments) {
+                return w.write_str("{}");
+            }
+            w.write_char('{')?;
+            for (i, (key, item)) in map.iter().enumerate() {
+                write_indent(w, indent, depth + 1)?;
+                if let Some(c) = comments.as_deref_mut() {
+                    c.path.push(key);
+                    c.write_before(w, indent, depth + 1)?;
+                }
+                write_string(w, key)?;
+                w.write_str(if indent.is_some() { ": " } else { ":" })?;
+                write_value(w, item, indent, depth + 1, comments.as_deref_mut())?;
+                if i + 1 < map.len() {
+                    w.write_char(',')?;
+                }
+                if let Some(c) = comments.as_deref_mut() {
+                    c.write_after(w)?;
+                    c.path.pop();
+                }
+            }
+            if let Some(c) = comments {
+                c.write_end(w, indent, depth + 1)?;
+            }
+            write_indent(w, indent, depth)?;
+            w.write_char('}')
+        }
+    }
+}
+
+/// compact JSON text, `JsonValue::from_str(&v.to_string())` gives back a value equal to `v`
+/// for every value produced by the parser in the JSON and JSONC dialects. The `Infinity` and
+/// `NaN` of JSON5 have no JSON form and are written as `null`
+impl fmt::Display for JsonValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, None, 0, None)
+    }
+}
+
+impl JsonValue<'_> {
+    /// multi-line JSON text with `indent` spaces per nesting level
+    pub fn to_string_pretty(&self, indent: usize) -> String {
+        let mut s = String::new();
+        write_value(&mut s, self, Some(indent), 0, None).expect("write to String never fail");
+        s
+    }
+
+    /// [`JsonValue::to_string_pretty`] with the comments of [`crate::parse_with_comments`] written
+    /// back at their anchor, comments whose anchor no longer exists are dropped
+    pub fn to_string_pretty_with_comments(
+        &self,
+        indent: usize,
+        comments: &[Comment<'_>],
+    ) -> String {
+        let mut writer = CommentWriter {
+            comments,
+            path: JsonPointer::root(),
+        };
+        let mut s = String::new();
+        writer
+            .write_before(&mut s, Some(indent), 0)
+            .and_then(|_| write_value(&mut s, self, Some(indent), 0, Some(&mut writer)))
+            .and_then(|_| writer.write_after(&mut s))
+            .expect("write to String never fail");
+        s
+    }
+}
+
+#[test]
+fn test_serialize() {
+    let value = JsonValue::Array(vec![
+        JsonValue::Null,
+        JsonValue::Boolean(true),
+        JsonValue::NumberI64(-1),
+        JsonValue::NumberF64(1.0),
+        JsonValue::NumberF64(0.1),
+        JsonValue::NumberF64(1e300),
+        JsonValue::String("\"\\/\u{08}\u{0C}\n\r\t\u{01}中文".into()),
+        JsonValue::Array(vec![]),
+        JsonValue::Map(
+            [("k".to_string(), JsonValue::Array(vec![]))]
+                .into_iter()
+                .collect(),
+        ),
+    ]);
+    let compact = r#"[null,true,-1,1.0,0.1,1e300,"\"\\/\b\f\n\r\t\u0001中文",[],{"k":[]}]"#;
+    assert_eq!(value.to_string(), compact);
+    assert_eq!(
+        value.to_string_pretty(2),
+        r#"[
+  null,
+  true,
+  -1,
+  1.0,
+  0.1,
+  1e300,
+  "\"\\/\b\f\n\r\t\u0001中文",
+  [],
+  {
+    "k": []
+  }
+]"#
+    );
+    assert_eq!(JsonValue::NumberF64(f64::NAN).to_string(), "null");
+
+    for text in [
+        compact,
+        r#"{"a": [1, -2, 18446744073709551615, -9223372036854775808, 2.5e-8, 1e21]}"#,
+        r#""😀 \u0000""#,
+    ] {
+        let value = JsonValue::from_str(text).unwrap();
+        assert_eq!(JsonValue::from_str(&value.to_string()), Ok(value.clone()));
+        assert_eq!(JsonValue::from_str(&value.to_string_pretty(4)), Ok(value));
+    }
+
+    let json5 = crate::json_parser::ParseOptions {
+        dialect: crate::json_parser::Dialect::Json5,
+        ..Default::default()
+    };
+    let value = JsonValue::from_str_with("[Infinity, -Infinity, NaN, 0x10]", json5).unwrap();
+    assert_eq!(value.to_string(), "[null,null,null,16]");
+}
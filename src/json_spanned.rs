@@ -0,0 +1,302 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_map::{dedupe_entries, JsonMap};
+use crate::json_parser::{
+    build_json_document, duplicate_key, DuplicateKeyPolicy, JsonBuilder, ParseOptions, Trivia,
+};
+use crate::json_pointer::{JsonPointer, PointerError};
+use crate::JsonValue;
+use nom::{
+    combinator::eof,
+    error::{context, ContextError, ParseError},
+};
+use std::borrow::Cow;
+use std::cell::Cell;
+
+/// a position in the source text, `line` and `column` start at 1 and `column` counts chars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// `start` is the first char of a node, `end` is just past its last char
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+/// an object key with the span of its quoted string
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedKey<'a> {
+    pub key: Cow<'a, str>,
+    pub span: Span,
+}
+
+impl AsRef<str> for SpannedKey<'_> {
+    fn as_ref(&self) -> &str {
+        &self.key
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedKind<'a> {
+    /// null, boolean, number or string
+    Scalar(JsonValue<'a>),
+    Array(Vec<SpannedValue<'a>>),
+    /// entries in document order, duplicate keys are already resolved by the parse options
+    Map(Vec<(SpannedKey<'a>, SpannedValue<'a>)>),
+}
+
+/// A JSON tree where every node remembers where it is in the source, for messages that point at
+/// the offending line of a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedValue<'a> {
+    pub kind: SpannedKind<'a>,
+    pub span: Span,
+}
+
+impl<'a> SpannedValue<'a> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &'a str) -> Result<Self, JsonError> {
+        Self::from_str_with(s, ParseOptions::default())
+    }
+
+    /// accepts the same documents as [`JsonValue::from_str_with`] and fails with the same error
+    pub fn from_str_with(s: &'a str, options: ParseOptions) -> Result<Self, JsonError> {
+        let mut builder = SpanBuilder {
+            locator: Locator::new(s),
+            policy: options.duplicate_key_policy,
+        };
+        let (rest, (_, value, _)) =
+            build_json_document::<JsonParseError, _>(s, options, &mut builder)
+                .map_err(|e| JsonError::from_nom(s, e))?;
+        context("end of input", eof)(rest).map_err(|e| JsonError::from_nom(s, e))?;
+        Ok(value)
+    }
+
+    /// drop the spans
+    pub fn into_value(self) -> JsonValue<'a> {
+        match self.kind {
+            SpannedKind::Scalar(value) => value,
+            SpannedKind::Array(items) => {
+                JsonValue::Array(items.into_iter().map(Self::into_value).collect())
+            }
+            SpannedKind::Map(entries) => {
+                let entries = entries
+                    .into_iter()
+                    .map(|(k, v)| (k.key, v.into_value()))
+                    .collect();
+                let map = JsonMap::from_entries_with(entries, DuplicateKeyPolicy::KeepAll)
+                    .expect("KeepAll never fails");
+                JsonValue::Map(map)
+            }
+        }
+    }
+
+    /// look up a node by JSON Pointer, e.g. to locate the path of a validation error
+    pub fn pointer(&self, pointer: &str) -> Result<&SpannedValue<'a>, PointerError> {
+        let pointer = pointer.parse::<JsonPointer>()?;
+        let mut node = self;
+        for (depth, token) in pointer.tokens().iter().enumerate() {
+            node = match &node.kind {
+                SpannedKind::Map(entries) => entries
+                    .iter()
+                    .find(|(k, _)| k.key == token.as_str())
+                    .map(|(_, v)| v)
+                    .ok_or_else(|| PointerError::MissingKey {
+                        pointer: pointer.prefix(depth),
+                    })?,
+                SpannedKind::Array(items) => &items[pointer.index(depth, items.len(), false)?],
+                SpannedKind::Scalar(_) => {
+                    return Err(PointerError::NotAContainer {
+                        pointer: pointer.prefix(depth),
+                    })
+                }
+            };
+        }
+        Ok(node)
+    }
+}
+
+/// turn the remaining input into a [`Location`] of the source. Nodes are located in increasing
+/// order, so each lookup only scans the text since the previous one
+struct Locator<'a> {
+    input: &'a str,
+    last: Cell<Location>,
+}
+
+impl<'a> Locator<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            last: Cell::new(Location {
+                offset: 0,
+                line: 1,
+                column: 1,
+            }),
+        }
+    }
+
+    fn locate(&self, rest: &str) -> Location {
+        let offset = self.input.len() - rest.len();
+        let mut location = self.last.get();
+        if offset < location.offset {
+            location = Location {
+                offset: 0,
+                line: 1,
+                column: 1,
+            };
+        }
+        for c in self.input[location.offset..offset].chars() {
+            match c {
+                '\n' => {
+                    location.line += 1;
+                    location.column = 1;
+                }
+                _ => location.column += 1,
+            }
+        }
+        location.offset = offset;
+        self.last.set(location);
+        location
+    }
+
+    fn span(&self, start: &str, end: &str) -> Span {
+        Span {
+            start: self.locate(start),
+            end: self.locate(end),
+        }
+    }
+}
+
+/// builds the spanned tree, `locator` is only moved forward: a container is located before its
+/// children
+struct SpanBuilder<'a> {
+    locator: Locator<'a>,
+    policy: DuplicateKeyPolicy,
+}
+
+type SpannedEntries<'a> = Vec<(SpannedKey<'a>, SpannedValue<'a>)>;
+
+impl<'a, E: ParseError<&'a str> + ContextError<&'a str>> JsonBuilder<'a, E> for SpanBuilder<'a> {
+    type Value = SpannedValue<'a>;
+    type Array = (Location, Vec<SpannedValue<'a>>);
+    /// the entries and where their keys start, to point at a duplicate
+    type Map = (Location, SpannedEntries<'a>, Vec<&'a str>);
+    type Key = (&'a str, SpannedKey<'a>);
+
+    fn scalar(&mut self, start: &'a str, value: JsonValue<'a>, rest: &'a str) -> Self::Value {
+        SpannedValue {
+            kind: SpannedKind::Scalar(value),
+            span: self.locator.span(start, rest),
+        }
+    }
+
+    fn start_array(&mut self, start: &'a str) -> Self::Array {
+        (self.locator.locate(start), Vec::new())
+    }
+
+    fn item(&mut self, array: &mut Self::Array, value: Self::Value, _trivia: Trivia<'a>) {
+        array.1.push(value);
+    }
+
+    fn end_array(&mut self, (start, items): Self::Array, _: &'a str, rest: &'a str) -> Self::Value {
+        let end = self.locator.locate(rest);
+        SpannedValue {
+            kind: SpannedKind::Array(items),
+            span: Span { start, end },
+        }
+    }
+
+    fn start_map(&mut self, start: &'a str) -> Self::Map {
+        (self.locator.locate(start), Vec::new(), Vec::new())
+    }
+
+    fn key(
+        &mut self,
+        _map: &mut Self::Map,
+        start: &'a str,
+        key: Cow<'a, str>,
+        rest: &'a str,
+    ) -> Result<Self::Key, nom::Err<E>> {
+        let span = self.locator.span(start, rest);
+        Ok((start, SpannedKey { key, span }))
+    }
+
+    fn member(&mut self, map: &mut Self::Map, key: Self::Key, value: Self::Value, _: Trivia<'a>) {
+        map.1.push((key.1, value));
+        map.2.push(key.0);
+    }
+
+    fn end_map(
+        &mut self,
+        (start, entries, positions): Self::Map,
+        _close: &'a str,
+        rest: &'a str,
+    ) -> Result<Self::Value, nom::Err<E>> {
+        let end = self.locator.locate(rest);
+        match dedupe_entries(entries, self.policy) {
+            Ok(entries) => Ok(SpannedValue {
+                kind: SpannedKind::Map(entries),
+                span: Span { start, end },
+            }),
+            Err(duplicate) => Err(duplicate_key(positions[duplicate])),
+        }
+    }
+}
+
+#[test]
+fn test_spanned_value() {
+    let text = "{\n  \"name\": \"é\",\n  \"ports\": [80, 443],\n  \"tls\": {\"on\": true}\n}\n";
+    let root = SpannedValue::from_str(text).unwrap();
+    let at = |pointer: &str| {
+        let span = root.pointer(pointer).unwrap().span;
+        (
+            &text[span.start.offset..span.end.offset],
+            span.start.line,
+            span.start.column,
+            span.end.line,
+            span.end.column,
+        )
+    };
+    assert_eq!(at(""), (text.trim_end(), 1, 1, 5, 2));
+    assert_eq!(at("/name"), ("\"é\"", 2, 11, 2, 14));
+    assert_eq!(at("/ports/1"), ("443", 3, 17, 3, 20));
+    assert_eq!(at("/tls"), ("{\"on\": true}", 4, 10, 4, 22));
+    assert_eq!(at("/tls/on"), ("true", 4, 17, 4, 21));
+    match &root.kind {
+        SpannedKind::Map(entries) => {
+            let key = &entries[1].0;
+            assert_eq!((key.key.as_ref(), key.span.start.line), ("ports", 3));
+            assert_eq!((key.span.start.column, key.span.end.column), (3, 10));
+        }
+        kind => panic!("{:?}", kind),
+    }
+    assert_eq!(
+        root.pointer("/ports/2"),
+        Err(PointerError::IndexOutOfBounds {
+            pointer: "/ports/2".to_string(),
+            len: 2
+        })
+    );
+    assert_eq!(
+        root.clone().into_value(),
+        JsonValue::from_str(text).unwrap()
+    );
+
+    // same duplicate key resolution and errors as the plain tree
+    for input in [r#"{"a": 1, "a": 2}"#, "[1,\n 2,", "{\"a\" 1}", "[1] x"] {
+        for policy in [DuplicateKeyPolicy::LastWins, DuplicateKeyPolicy::Error] {
+            let options = ParseOptions {
+                duplicate_key_policy: policy,
+                ..ParseOptions::default()
+            };
+            assert_eq!(
+                SpannedValue::from_str_with(input, options).map(SpannedValue::into_value),
+                JsonValue::from_str_with(input, options)
+            );
+        }
+    }
+}
@@ -0,0 +1,657 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_map::JsonMap;
+use crate::json_parser::{
+    number_from_literal, parse_json_value, parse_number_literal, parse_string, split, Dialect,
+    DuplicateKeyPolicy, ParseOptions, RootMode,
+};
+use crate::JsonValue;
+use nom::{
+    branch::alt,
+    bytes::{
+        complete,
+        streaming::{tag, take_while1},
+    },
+    character::streaming::{anychar, char as char_},
+    combinator::{all_consuming, map, recognize, value},
+    error::ParseError,
+    multi::many0_count,
+    sequence::{delimited, pair},
+    IResult,
+};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// lexical token of the streaming grammar, strings and numbers are still raw text here
+#[derive(Debug, Clone, Copy)]
+enum Token<'a> {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Colon,
+    Comma,
+    Null,
+    Boolean(bool),
+    String(&'a str),
+    Number(&'a str),
+}
+
+/// string token including both quotes, its content is checked later by [`parse_string`]
+fn string_token<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(delimited(
+        char_('"'),
+        many0_count(alt((
+            take_while1(|c| c != '"' && c != '\\'),
+            recognize(pair(char_('\\'), anychar)),
+        ))),
+        char_('"'),
+    ))(i)
+}
+
+/// chars that end a literal token, `truefalse` or `1true` isn't two values
+fn is_delimiter(c: char) -> bool {
+    " \t\r\n{}[],:".contains(c)
+}
+
+fn is_number_char(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')
+}
+
+/// the nom streaming parsers return Incomplete when a token may continue in the next chunk,
+/// only at the end of the stream a number is allowed to stop at the end of the buffer
+fn parse_token<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    eof: bool,
+) -> IResult<&'a str, Token<'a>, E> {
+    alt((
+        value(Token::BeginObject, char_('{')),
+        value(Token::EndObject, char_('}')),
+        value(Token::BeginArray, char_('[')),
+        value(Token::EndArray, char_(']')),
+        value(Token::Colon, char_(':')),
+        value(Token::Comma, char_(',')),
+        value(Token::Null, tag("null")),
+        value(Token::Boolean(true), tag("true")),
+        value(Token::Boolean(false), tag("false")),
+        map(string_token, Token::String),
+        map(
+            |i| match eof {
+                true => complete::take_while1(is_number_char)(i),
+                false => take_while1(is_number_char)(i),
+            },
+            Token::Number,
+        ),
+    ))(i)
+}
+
+/// a token with its string or number already decoded
+enum Item {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Colon,
+    Comma,
+    String(Cow<'static, str>),
+    Scalar(JsonValue<'static>),
+}
+
+/// what the last token of an open container was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArrayState {
+    Open,
+    Value,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MapState {
+    Open,
+    Key,
+    Colon,
+    Value,
+    Comma,
+}
+
+/// a container whose closing bracket hasn't been read yet
+enum Frame {
+    Array {
+        items: Vec<JsonValue<'static>>,
+        state: ArrayState,
+    },
+    Map {
+        entries: Vec<(Cow<'static, str>, JsonValue<'static>)>,
+        /// only filled with [`DuplicateKeyPolicy::Error`] to report the repeated key where it is
+        seen: HashSet<Cow<'static, str>>,
+        key: Option<Cow<'static, str>>,
+        state: MapState,
+    },
+}
+
+/// result of [`JsonStreamParser::next_value`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamStatus {
+    /// a complete root value, or an item of the root array with
+    /// [`JsonStreamParser::stream_root_array`]
+    Value(JsonValue<'static>),
+    /// the buffered input ends inside a value, feed more chunks
+    Incomplete,
+    /// [`JsonStreamParser::finish`] was called and every value has been returned
+    Done,
+}
+
+/// Incremental parser fed with chunks of bytes, e.g. from a socket or a file.
+///
+/// Tokens are read with nom's `streaming` parsers and the open containers are kept on an explicit
+/// stack, so only the unfinished token is buffered between chunks. The stream may hold any number
+/// of whitespace separated root values.
+///
+/// Only plain JSON is read, with a JSON5 or JSONC [`Dialect`] every [`Self::next_value`] fails
+pub struct JsonStreamParser {
+    options: ParseOptions,
+    stream_root_array: bool,
+    buf: String,
+    /// bytes of `buf` already consumed
+    pos: usize,
+    /// an UTF-8 sequence cut by the chunk boundary, completed by the next chunk
+    utf8_tail: Vec<u8>,
+    /// stream offset of the first invalid UTF-8 byte, nothing from there on is buffered so
+    /// `buf` ends right before it
+    invalid_utf8: Option<usize>,
+    eof: bool,
+    /// offset, line and column of `buf[0]` in the whole stream
+    base: (usize, usize, usize),
+    stack: Vec<Frame>,
+}
+
+impl Default for JsonStreamParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonStreamParser {
+    pub fn new() -> Self {
+        Self::with_options(ParseOptions::default())
+    }
+
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            options,
+            stream_root_array: false,
+            buf: String::new(),
+            pos: 0,
+            utf8_tail: Vec::new(),
+            invalid_utf8: None,
+            eof: false,
+            base: (0, 1, 1),
+            stack: Vec::new(),
+        }
+    }
+
+    /// return the items of a root array one by one instead of the whole array, so an export
+    /// like `[{...}, {...}, ...]` is processed without holding every record in memory
+    pub fn stream_root_array(mut self, enabled: bool) -> Self {
+        self.stream_root_array = enabled;
+        self
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        if self.invalid_utf8.is_some() {
+            return;
+        }
+        self.compact();
+        let mut bytes = std::mem::take(&mut self.utf8_tail);
+        bytes.extend_from_slice(chunk);
+        match std::str::from_utf8(&bytes) {
+            Ok(text) => self.buf.push_str(text),
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                self.buf
+                    .push_str(std::str::from_utf8(valid).expect("checked by valid_up_to"));
+                match e.error_len() {
+                    Some(_) => self.invalid_utf8 = Some(self.base.0 + self.buf.len()),
+                    None => self.utf8_tail = rest.to_vec(),
+                }
+            }
+        }
+    }
+
+    /// no more chunks will come, a value still open after this is an error
+    pub fn finish(&mut self) {
+        self.eof = true;
+    }
+
+    /// drop the consumed text so the buffer only hold the unfinished token
+    fn compact(&mut self) {
+        if self.pos == 0 {
+            return;
+        }
+        let consumed = &self.buf[..self.pos];
+        let (offset, line, column) = self.base;
+        self.base = match consumed.rfind('\n') {
+            Some(last) => (
+                offset + consumed.len(),
+                line + consumed.matches('\n').count(),
+                consumed[last + 1..].chars().count() + 1,
+            ),
+            None => (
+                offset + consumed.len(),
+                line,
+                column + consumed.chars().count(),
+            ),
+        };
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+    }
+
+    fn error(&self, pos: usize, expected: &str) -> JsonError {
+        let (offset, line, column) = self.base;
+        JsonError::at(&self.buf, pos, Some(expected.to_string())).shift(offset, line, column)
+    }
+
+    /// the bytes after the buffered text can never be decoded
+    fn bad_utf8(&self) -> bool {
+        self.invalid_utf8.is_some() || (self.eof && !self.utf8_tail.is_empty())
+    }
+
+    /// error at the invalid byte, or at the end for a sequence cut by the end of the stream
+    fn utf8_error(&self) -> JsonError {
+        let pos = self
+            .invalid_utf8
+            .map_or(self.buf.len(), |offset| offset - self.base.0);
+        self.error(pos, "valid UTF-8")
+    }
+
+    fn expected(&self) -> &'static str {
+        match self.stack.last() {
+            None if self.options.root_mode == RootMode::Rfc4627 => "`{` or `[`",
+            None => "a JSON value",
+            Some(Frame::Array { state, .. }) => match state {
+                ArrayState::Open | ArrayState::Comma => "a JSON value",
+                ArrayState::Value => "`,` or `]`",
+            },
+            Some(Frame::Map { state, .. }) => match state {
+                MapState::Open | MapState::Comma => "a string key",
+                MapState::Key => "`:`",
+                MapState::Colon => "a JSON value",
+                MapState::Value => "`,` or `}`",
+            },
+        }
+    }
+
+    /// what may follow a value that is complete, a literal like `true` or `1` can't be directly
+    /// followed by another one
+    fn expected_after_value(&self) -> &'static str {
+        match self.stack.last() {
+            None => "whitespace",
+            Some(Frame::Array { .. }) => "`,` or `]`",
+            Some(Frame::Map { .. }) => "`,` or `}`",
+        }
+    }
+
+    /// parse as many tokens as the buffer holds until a root value is complete
+    pub fn next_value(&mut self) -> Result<StreamStatus, JsonError> {
+        if self.options.dialect != Dialect::Json {
+            return Err(self.error(self.pos, "the JSON dialect"));
+        }
+        loop {
+            let (rest, _) =
+                split::<JsonParseError>(&self.buf[self.pos..]).expect("split never fail");
+            self.pos = self.buf.len() - rest.len();
+            if rest.is_empty() {
+                if self.bad_utf8() {
+                    return Err(self.utf8_error());
+                }
+                if !self.eof {
+                    return Ok(StreamStatus::Incomplete);
+                }
+                if self.stack.is_empty() {
+                    return Ok(StreamStatus::Done);
+                }
+                return Err(self.error(self.pos, self.expected()));
+            }
+            let start = self.pos;
+            let (item, len, literal) = match parse_token::<JsonParseError>(rest, self.eof) {
+                Ok((after, token)) => {
+                    let literal =
+                        matches!(token, Token::Null | Token::Boolean(_) | Token::Number(_));
+                    // `true` at the end of the buffer may still become `truex`
+                    if literal && after.is_empty() && !self.eof && !self.bad_utf8() {
+                        return Ok(StreamStatus::Incomplete);
+                    }
+                    let len = rest.len() - after.len();
+                    (self.decode(token, start)?, len, literal)
+                }
+                Err(nom::Err::Incomplete(_)) if self.bad_utf8() => return Err(self.utf8_error()),
+                Err(nom::Err::Incomplete(_)) if !self.eof => return Ok(StreamStatus::Incomplete),
+                // the complete grammar tells what the truncated token is missing
+                Err(nom::Err::Incomplete(_)) => {
+                    return Err(match parse_json_value::<JsonParseError>(rest) {
+                        Err(e) => {
+                            let e = JsonError::from_nom(rest, e);
+                            self.error(start + e.offset(), e.expected().unwrap_or("more input"))
+                        }
+                        Ok(_) => self.error(self.buf.len(), "more input"),
+                    });
+                }
+                Err(_) => return Err(self.error(start, self.expected())),
+            };
+            self.pos += len;
+            let value = self.apply(item, start)?;
+            if literal && self.pos == self.buf.len() && self.bad_utf8() {
+                return Err(self.utf8_error());
+            }
+            if literal
+                && !self.buf[self.pos..].starts_with(is_delimiter)
+                && self.pos < self.buf.len()
+            {
+                return Err(self.error(self.pos, self.expected_after_value()));
+            }
+            if let Some(value) = value {
+                return Ok(StreamStatus::Value(value));
+            }
+        }
+    }
+
+    fn decode(&self, token: Token<'_>, start: usize) -> Result<Item, JsonError> {
+        Ok(match token {
+            Token::BeginObject => Item::BeginObject,
+            Token::EndObject => Item::EndObject,
+            Token::BeginArray => Item::BeginArray,
+            Token::EndArray => Item::EndArray,
+            Token::Colon => Item::Colon,
+            Token::Comma => Item::Comma,
+            Token::Null => Item::Scalar(JsonValue::Null),
+            Token::Boolean(b) => Item::Scalar(JsonValue::Boolean(b)),
+            Token::String(raw) => match all_consuming(parse_string::<JsonParseError>)(raw) {
+                Ok((_, s)) => Item::String(Cow::Owned(s.into_owned())),
+                Err(e) => {
+                    let e = JsonError::from_nom(raw, e);
+                    return Err(
+                        self.error(start + e.offset(), e.expected().unwrap_or("a valid string"))
+                    );
+                }
+            },
+            Token::Number(raw) => {
+                match all_consuming(parse_number_literal::<JsonParseError>)(raw) {
+                    Ok(_) => match number_from_literal(raw, self.options.number_mode) {
+                        Some(number) => Item::Scalar(number),
+                        None => return Err(self.error(start, "a number in f64 range")),
+                    },
+                    Err(e) => {
+                        let e = JsonError::from_nom(raw, e);
+                        return Err(self
+                            .error(start + e.offset(), e.expected().unwrap_or("a valid number")));
+                    }
+                }
+            }
+        })
+    }
+
+    /// feed one token to the container stack, return a value once a root value is complete
+    fn apply(&mut self, item: Item, start: usize) -> Result<Option<JsonValue<'static>>, JsonError> {
+        let expects_value = match self.stack.last() {
+            None => true,
+            Some(Frame::Array { state, .. }) => *state != ArrayState::Value,
+            Some(Frame::Map { state, .. }) => *state == MapState::Colon,
+        };
+        let scalar_allowed = !self.stack.is_empty() || self.options.root_mode == RootMode::AnyValue;
+        let policy = self.options.duplicate_key_policy;
+        match (self.stack.last_mut(), item) {
+            (_, Item::BeginArray) if expects_value => {
+                self.stack.push(Frame::Array {
+                    items: Vec::new(),
+                    state: ArrayState::Open,
+                });
+                Ok(None)
+            }
+            (_, Item::BeginObject) if expects_value => {
+                self.stack.push(Frame::Map {
+                    entries: Vec::new(),
+                    seen: HashSet::new(),
+                    key: None,
+                    state: MapState::Open,
+                });
+                Ok(None)
+            }
+            (
+                Some(Frame::Map {
+                    seen,
+                    key,
+                    state: state @ (MapState::Open | MapState::Comma),
+                    ..
+                }),
+                Item::String(s),
+            ) => {
+                if policy == DuplicateKeyPolicy::Error && !seen.insert(s.clone()) {
+                    return Err(self.error(start, "a unique key"));
+                }
+                *key = Some(s);
+                *state = MapState::Key;
+                Ok(None)
+            }
+            (_, Item::String(s)) if expects_value && scalar_allowed => {
+                Ok(self.complete_value(JsonValue::String(s)))
+            }
+            (_, Item::Scalar(value)) if expects_value && scalar_allowed => {
+                Ok(self.complete_value(value))
+            }
+            (
+                Some(Frame::Map {
+                    state: state @ MapState::Key,
+                    ..
+                }),
+                Item::Colon,
+            ) => {
+                *state = MapState::Colon;
+                Ok(None)
+            }
+            (
+                Some(Frame::Array {
+                    state: state @ ArrayState::Value,
+                    ..
+                }),
+                Item::Comma,
+            ) => {
+                *state = ArrayState::Comma;
+                Ok(None)
+            }
+            (
+                Some(Frame::Map {
+                    state: state @ MapState::Value,
+                    ..
+                }),
+                Item::Comma,
+            ) => {
+                *state = MapState::Comma;
+                Ok(None)
+            }
+            (
+                Some(Frame::Array {
+                    state: ArrayState::Open | ArrayState::Value,
+                    ..
+                }),
+                Item::EndArray,
+            ) => {
+                let Some(Frame::Array { items, .. }) = self.stack.pop() else {
+                    unreachable!()
+                };
+                if self.stream_root_array && self.stack.is_empty() {
+                    return Ok(None);
+                }
+                Ok(self.complete_value(JsonValue::Array(items)))
+            }
+            (
+                Some(Frame::Map {
+                    state: MapState::Open | MapState::Value,
+                    ..
+                }),
+                Item::EndObject,
+            ) => {
+                let Some(Frame::Map { entries, .. }) = self.stack.pop() else {
+                    unreachable!()
+                };
+                let map = JsonMap::from_entries_with(entries, policy)
+                    .expect("duplicate keys are rejected when they are read");
+                Ok(self.complete_value(JsonValue::Map(map)))
+            }
+            _ => Err(self.error(start, self.expected())),
+        }
+    }
+
+    fn complete_value(&mut self, value: JsonValue<'static>) -> Option<JsonValue<'static>> {
+        let is_root_array = self.stream_root_array && self.stack.len() == 1;
+        match self.stack.last_mut() {
+            None => Some(value),
+            Some(Frame::Array { items, state }) => {
+                *state = ArrayState::Value;
+                if is_root_array {
+                    return Some(value);
+                }
+                items.push(value);
+                None
+            }
+            Some(Frame::Map {
+                entries,
+                key,
+                state,
+                ..
+            }) => {
+                *state = MapState::Value;
+                entries.push((key.take().expect("a key precede every map value"), value));
+                None
+            }
+        }
+    }
+}
+
+#[test]
+fn test_json_stream() {
+    use crate::json_parser::NumberMode;
+
+    // values split at every byte, including inside the multi-byte chars
+    let text = "{\"a\": [1, 2.5, \"中文\"], \"b\": null} true\n -12 \"x\" []";
+    let mut parser = JsonStreamParser::new();
+    let mut values = Vec::new();
+    for byte in text.as_bytes() {
+        parser.feed(std::slice::from_ref(byte));
+        loop {
+            match parser.next_value().unwrap() {
+                StreamStatus::Value(value) => values.push(value.to_string()),
+                StreamStatus::Incomplete => break,
+                StreamStatus::Done => unreachable!(),
+            }
+        }
+    }
+    parser.finish();
+    assert_eq!(parser.next_value(), Ok(StreamStatus::Done));
+    assert_eq!(
+        values,
+        [
+            r#"{"a":[1,2.5,"中文"],"b":null}"#,
+            "true",
+            "-12",
+            r#""x""#,
+            "[]"
+        ]
+    );
+
+    // a number at the very end needs finish to be complete
+    let mut parser = JsonStreamParser::with_options(ParseOptions {
+        number_mode: NumberMode::ArbitraryPrecision,
+        ..ParseOptions::default()
+    });
+    parser.feed(b"1.50");
+    assert_eq!(parser.next_value(), Ok(StreamStatus::Incomplete));
+    parser.finish();
+    let value = parser.next_value().unwrap();
+    assert_eq!(
+        value,
+        StreamStatus::Value(JsonValue::from_str_with("1.50", parser.options).unwrap())
+    );
+
+    let mut parser = JsonStreamParser::new().stream_root_array(true);
+    parser.feed(b"[{\"id\": 1}, [2], 3");
+    let mut items = Vec::new();
+    while let StreamStatus::Value(value) = parser.next_value().unwrap() {
+        items.push(value.to_string());
+    }
+    assert_eq!(items, [r#"{"id":1}"#, "[2]"]);
+    parser.feed(b"]");
+    parser.finish();
+    assert_eq!(
+        parser.next_value(),
+        Ok(StreamStatus::Value(JsonValue::NumberU64(3)))
+    );
+    assert_eq!(parser.next_value(), Ok(StreamStatus::Done));
+
+    let error = |chunks: &[&str]| {
+        let mut parser = JsonStreamParser::new();
+        for chunk in chunks {
+            parser.feed(chunk.as_bytes());
+        }
+        parser.finish();
+        loop {
+            match parser.next_value() {
+                Ok(StreamStatus::Done) => unreachable!(),
+                Ok(_) => {}
+                Err(e) => return (e.line(), e.column(), e.expected().unwrap().to_string()),
+            }
+        }
+    };
+    assert_eq!(error(&["[1,", "\n 2 3]"]), (2, 4, "`,` or `]`".to_string()));
+    assert_eq!(error(&["{\"a\"", " 1}"]), (1, 6, "`:`".to_string()));
+    assert_eq!(error(&["[1, ", "]"]), (1, 5, "a JSON value".to_string()));
+    assert_eq!(error(&["[\"ab", "c"]), (1, 6, "`\"`".to_string()));
+    assert_eq!(error(&["{\"a\": [tr"]), (1, 8, "a JSON value".to_string()));
+    assert_eq!(error(&["[1.]"]), (1, 4, "a digit".to_string()));
+    assert_eq!(error(&["tru", "efalse"]), (1, 5, "whitespace".to_string()));
+    assert_eq!(error(&["[1", "true]"]), (1, 3, "`,` or `]`".to_string()));
+    assert_eq!(
+        error(&["{\"a\": null1}"]),
+        (1, 11, "`,` or `}`".to_string())
+    );
+    assert_eq!(error(&["{\"a\": {"]), (1, 8, "a string key".to_string()));
+    assert_eq!(
+        error(&["\"\u{e4}\"", "\"\u{e4}"]),
+        (1, 6, "`\"`".to_string())
+    );
+
+    let mut parser = JsonStreamParser::new();
+    parser.feed(b"[\"\xff\"]");
+    assert_eq!(
+        parser.next_value().unwrap_err().expected(),
+        Some("valid UTF-8")
+    );
+
+    // nothing after an invalid byte is read, the error points at it
+    for (chunks, values, offset) in [
+        (&[&b"[1, \xff"[..], b"2]", b"3", b" "][..], 0, 4),
+        (&[b"true\xff", b" "], 0, 4),
+        (&[b"1 [2]\xff", b"[3]"], 2, 5),
+        (&[b"\"a\"", b"\xe4"], 1, 3),
+    ] {
+        let mut parser = JsonStreamParser::new();
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+        parser.finish();
+        for _ in 0..values {
+            assert!(matches!(parser.next_value(), Ok(StreamStatus::Value(_))));
+        }
+        let e = parser.next_value().unwrap_err();
+        assert_eq!((e.offset(), e.expected()), (offset, Some("valid UTF-8")));
+    }
+
+    // JSON5 and JSONC aren't read by the tokenizer
+    let mut parser = JsonStreamParser::with_options(ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    });
+    parser.feed(b"[1, 2,]");
+    assert_eq!(
+        parser.next_value().unwrap_err().expected(),
+        Some("the JSON dialect")
+    );
+}
@@ -0,0 +1,395 @@
+use crate::json_map::JsonMap;
+use crate::raw_number::RawNumber;
+use crate::JsonValue;
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Index;
+
+/// what [`Index`] returns for a missing key or index
+static NULL: JsonValue<'static> = JsonValue::Null;
+
+impl<'a> JsonValue<'a> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// any number, large integers and [`RawNumber`] are rounded to the nearest f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::NumberI64(n) => Some(*n as f64),
+            JsonValue::NumberU64(n) => Some(*n as f64),
+            JsonValue::NumberF64(n) => Some(*n),
+            JsonValue::Number(n) => n.to_f64(),
+            _ => None,
+        }
+    }
+
+    /// an integer that fits in i64, a float such as `1.0` is `None` even if it is integral
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::NumberI64(n) => Some(*n),
+            JsonValue::NumberU64(n) => i64::try_from(*n).ok(),
+            JsonValue::Number(n) => n.to_i128().and_then(|n| i64::try_from(n).ok()),
+            _ => None,
+        }
+    }
+
+    /// a non negative integer that fits in u64, see [`JsonValue::as_i64`]
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::NumberI64(n) => u64::try_from(*n).ok(),
+            JsonValue::NumberU64(n) => Some(*n),
+            JsonValue::Number(n) => n.to_i128().and_then(|n| u64::try_from(n).ok()),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue<'a>]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&JsonMap<'a>> {
+        match self {
+            JsonValue::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// the value of `key` when this is an object, `None` for any other value
+    pub fn get(&self, key: &str) -> Option<&JsonValue<'a>> {
+        self.as_object()?.get(key)
+    }
+
+    /// JSON type name for error messages, e.g. `an array`
+    fn kind(&self) -> String {
+        match self {
+            JsonValue::Array(_) => "an array".to_string(),
+            JsonValue::Map(_) => "an object".to_string(),
+            scalar => format!("`{}`", scalar),
+        }
+    }
+}
+
+/// `value["key"]`, null when the value isn't an object or has no such key
+impl<'a> Index<&str> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, key: &str) -> &JsonValue<'a> {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// `value[0]`, null when the value isn't an array or the index is out of bounds
+impl<'a> Index<usize> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, index: usize) -> &JsonValue<'a> {
+        self.as_array()
+            .and_then(|items| items.get(index))
+            .unwrap_or(&NULL)
+    }
+}
+
+/// a [`TryFrom<JsonValue>`] conversion to a type the value doesn't hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueTypeError {
+    expected: &'static str,
+    found: String,
+}
+
+impl ValueTypeError {
+    /// the type asked for, e.g. `i64`
+    pub fn expected(&self) -> &str {
+        self.expected
+    }
+}
+
+impl fmt::Display for ValueTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for ValueTypeError {}
+
+macro_rules! impl_try_from {
+    ($($ty:ty => $expected:literal, $convert:expr;)*) => {
+        $(
+            impl<'a> TryFrom<JsonValue<'a>> for $ty {
+                type Error = ValueTypeError;
+
+                fn try_from(value: JsonValue<'a>) -> Result<Self, ValueTypeError> {
+                    let convert: fn(&JsonValue<'a>) -> Option<$ty> = $convert;
+                    convert(&value).ok_or_else(|| ValueTypeError {
+                        expected: $expected,
+                        found: value.kind(),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from! {
+    bool => "a boolean", JsonValue::as_bool;
+    i64 => "an i64", JsonValue::as_i64;
+    u64 => "a u64", JsonValue::as_u64;
+    f64 => "a number", JsonValue::as_f64;
+    String => "a string", |v| v.as_str().map(str::to_string);
+}
+
+impl<'a> TryFrom<JsonValue<'a>> for Cow<'a, str> {
+    type Error = ValueTypeError;
+
+    /// keeps a string borrowed from the input borrowed
+    fn try_from(value: JsonValue<'a>) -> Result<Self, ValueTypeError> {
+        match value {
+            JsonValue::String(s) => Ok(s),
+            value => Err(ValueTypeError {
+                expected: "a string",
+                found: value.kind(),
+            }),
+        }
+    }
+}
+
+/// integers are stored like the parser does: u64 when not negative, otherwise i64
+macro_rules! impl_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for JsonValue<'_> {
+                fn from(n: $ty) -> Self {
+                    match u64::try_from(n) {
+                        Ok(n) => JsonValue::NumberU64(n),
+                        Err(_) => JsonValue::NumberI64(n as i64),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<()> for JsonValue<'_> {
+    fn from(_: ()) -> Self {
+        JsonValue::Null
+    }
+}
+
+impl From<bool> for JsonValue<'_> {
+    fn from(b: bool) -> Self {
+        JsonValue::Boolean(b)
+    }
+}
+
+impl From<f32> for JsonValue<'_> {
+    fn from(n: f32) -> Self {
+        JsonValue::NumberF64(n.into())
+    }
+}
+
+impl From<f64> for JsonValue<'_> {
+    fn from(n: f64) -> Self {
+        JsonValue::NumberF64(n)
+    }
+}
+
+impl From<RawNumber> for JsonValue<'_> {
+    fn from(n: RawNumber) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+impl<'a> From<&'a str> for JsonValue<'a> {
+    fn from(s: &'a str) -> Self {
+        JsonValue::String(Cow::Borrowed(s))
+    }
+}
+
+impl From<String> for JsonValue<'_> {
+    fn from(s: String) -> Self {
+        JsonValue::String(Cow::Owned(s))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for JsonValue<'a> {
+    fn from(s: Cow<'a, str>) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl<'a, T: Into<JsonValue<'a>>> From<Vec<T>> for JsonValue<'a> {
+    fn from(items: Vec<T>) -> Self {
+        JsonValue::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<'a> From<JsonMap<'a>> for JsonValue<'a> {
+    fn from(map: JsonMap<'a>) -> Self {
+        JsonValue::Map(map)
+    }
+}
+
+/// `None` is null
+impl<'a, T: Into<JsonValue<'a>>> From<Option<T>> for JsonValue<'a> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+/// Build a [`JsonValue`] with JSON syntax, e.g. `json!({"name": "demo", "ports": [port, 443]})`
+/// for the expected value of a test.
+///
+/// Any other value is an expression converted with `From`, a key is a string literal or an
+/// expression in parentheses. A repeated key keeps its last value
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::JsonValue::Null
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::JsonValue::Array($crate::json!(@array [] $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::JsonValue::Map(
+            $crate::json!(@object [] $($tt)*)
+                .into_iter()
+                .collect::<$crate::JsonMap>(),
+        )
+    };
+    ($other:expr) => {
+        $crate::JsonValue::from($other)
+    };
+
+    // array items are munched one at a time so an item can be any expression
+    (@array [$($items:expr,)*]) => {
+        vec![$($items,)*]
+    };
+    (@array [$($items:expr,)*] , $($rest:tt)*) => {
+        $crate::json!(@array [$($items,)*] $($rest)*)
+    };
+    (@array [$($items:expr,)*] null $($rest:tt)*) => {
+        $crate::json!(@array [$($items,)* $crate::json!(null),] $($rest)*)
+    };
+    (@array [$($items:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json!(@array [$($items,)* $crate::json!([$($array)*]),] $($rest)*)
+    };
+    (@array [$($items:expr,)*] {$($map:tt)*} $($rest:tt)*) => {
+        $crate::json!(@array [$($items,)* $crate::json!({$($map)*}),] $($rest)*)
+    };
+    (@array [$($items:expr,)*] $next:expr , $($rest:tt)*) => {
+        $crate::json!(@array [$($items,)* $crate::json!($next),] $($rest)*)
+    };
+    (@array [$($items:expr,)*] $last:expr) => {
+        $crate::json!(@array [$($items,)* $crate::json!($last),])
+    };
+
+    (@object [$($entries:expr,)*]) => {
+        ::std::vec::Vec::<(::std::borrow::Cow<'_, str>, $crate::JsonValue<'_>)>::from([
+            $($entries,)*
+        ])
+    };
+    (@object [$($entries:expr,)*] , $($rest:tt)*) => {
+        $crate::json!(@object [$($entries,)*] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : null $($rest:tt)*) => {
+        $crate::json!(@object [$($entries,)* $crate::json!(@entry $key, null),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json!(@object [$($entries,)* $crate::json!(@entry $key, [$($array)*]),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : {$($map:tt)*} $($rest:tt)*) => {
+        $crate::json!(@object [$($entries,)* $crate::json!(@entry $key, {$($map)*}),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : $value:expr , $($rest:tt)*) => {
+        $crate::json!(@object [$($entries,)* $crate::json!(@entry $key, $value),] $($rest)*)
+    };
+    (@object [$($entries:expr,)*] $key:tt : $value:expr) => {
+        $crate::json!(@object [$($entries,)* $crate::json!(@entry $key, $value),])
+    };
+    (@entry $key:tt, $($value:tt)+) => {
+        (::std::borrow::Cow::<str>::from($key), $crate::json!($($value)+))
+    };
+}
+
+#[test]
+fn test_json_value_access() {
+    let text = r#"{"name": "demo", "port": 8080, "ratio": 0.5, "offset": -3,
+        "tags": ["a", null, {"deep": [true]}], "big": 18446744073709551615}"#;
+    let value = JsonValue::from_str(text).unwrap();
+    let tag = "a";
+    assert_eq!(
+        value,
+        json!({
+            "name": "demo",
+            "port": 8080,
+            "ratio": 0.5,
+            "offset": -3,
+            "tags": [tag, null, {"deep": [1 > 0]}],
+            ("big"): u64::MAX,
+        })
+    );
+
+    assert_eq!(value["name"].as_str(), Some("demo"));
+    assert_eq!(value["port"].as_i64(), Some(8080));
+    assert_eq!(value["port"].as_f64(), Some(8080.0));
+    assert_eq!(value["ratio"].as_i64(), None);
+    assert_eq!(value["offset"].as_u64(), None);
+    assert_eq!(value["big"].as_i64(), None);
+    assert_eq!(value["big"].as_u64(), Some(u64::MAX));
+    assert_eq!(value["tags"][2]["deep"][0].as_bool(), Some(true));
+    assert!(value["tags"][1].is_null());
+    // missing keys and indexes and the wrong container are null too
+    assert!(value["nope"].is_null() && value["tags"][9].is_null() && value["port"][0].is_null());
+    assert_eq!(value.get("nope"), None);
+    assert_eq!(value["tags"].as_array().map(<[_]>::len), Some(3));
+    assert_eq!(value.as_object().map(JsonMap::len), Some(6));
+    assert_eq!(value.as_array(), None);
+
+    assert_eq!(i64::try_from(value["offset"].clone()), Ok(-3));
+    assert_eq!(
+        String::try_from(value["name"].clone()).as_deref(),
+        Ok("demo")
+    );
+    assert!(matches!(
+        Cow::try_from(value["name"].clone()),
+        Ok(Cow::Borrowed("demo"))
+    ));
+    assert_eq!(
+        i64::try_from(value["ratio"].clone())
+            .unwrap_err()
+            .to_string(),
+        "expected an i64, found `0.5`"
+    );
+    assert_eq!(
+        bool::try_from(value["tags"].clone())
+            .unwrap_err()
+            .to_string(),
+        "expected a boolean, found an array"
+    );
+
+    assert_eq!(JsonValue::from(-1i8), JsonValue::NumberI64(-1));
+    assert_eq!(JsonValue::from(Some(vec![1u8])), json!([1]));
+    assert_eq!(JsonValue::from(None::<bool>), json!(null));
+    assert_eq!(json!([]), JsonValue::Array(vec![]));
+    assert_eq!(json!({}).to_string(), "{}");
+    assert_eq!(json!({"a": 1, "a": 2}), json!({"a": 2}));
+}
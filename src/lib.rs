@@ -1,2 +1,41 @@
-#[cfg(test)]
-mod json_parser;
+pub mod json5;
+pub mod json_comments;
+pub mod json_cst;
+pub mod json_error;
+pub mod json_events;
+pub mod json_lines;
+pub mod json_map;
+pub mod json_parser;
+pub mod json_patch;
+pub mod json_path;
+pub mod json_pointer;
+pub mod json_recovery;
+pub mod json_schema;
+pub mod json_sequence;
+#[cfg(feature = "serde")]
+pub mod json_serde;
+pub mod json_serializer;
+pub mod json_spanned;
+pub mod json_stream;
+pub mod json_value;
+pub mod raw_number;
+
+pub use json_comments::{parse_with_comments, Comment, CommentAnchor};
+pub use json_cst::CstDocument;
+pub use json_error::JsonError;
+pub use json_events::{parse_events, JsonEvent};
+pub use json_lines::JsonLinesReader;
+pub use json_map::JsonMap;
+pub use json_parser::JsonValue;
+pub use json_patch::{apply_merge_patch, apply_patch, diff, PatchError};
+pub use json_path::JsonPath;
+pub use json_pointer::{JsonPointer, PointerError};
+pub use json_recovery::parse_recovering;
+pub use json_schema::{JsonSchema, SchemaError, ValidationError};
+pub use json_sequence::{Framing, JsonSequence};
+#[cfg(feature = "serde")]
+pub use json_serde::{from_str, from_value, to_value, SerdeError};
+pub use json_spanned::{Span, SpannedKind, SpannedValue};
+pub use json_stream::{JsonStreamParser, StreamStatus};
+pub use json_value::ValueTypeError;
+pub use raw_number::RawNumber;
@@ -0,0 +1,207 @@
+use crate::json_error::{JsonError, JsonParseError};
+use crate::json_parser::parse_number_literal;
+use nom::combinator::all_consuming;
+use std::{fmt, str::FromStr};
+
+/// JSON number literal kept as its original text, so 128-bit IDs and decimals like `0.1` never
+/// go through a lossy f64
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawNumber(pub(crate) String);
+
+/// the literal rewritten as `digits * 10^exponent`, digits has no leading or trailing zero
+/// and is empty when the number is zero
+struct Decimal {
+    negative: bool,
+    digits: String,
+    exponent: i64,
+}
+
+/// most zeros `RawNumber::to_decimal_string` writes out for the exponent
+const MAX_PADDING_ZEROS: usize = 4096;
+
+impl RawNumber {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn decimal(&self) -> Decimal {
+        let (negative, literal) = match self.0.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, self.0.as_str()),
+        };
+        let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+            // exponent overflow i64 only happens on absurd literal, saturate it
+            Some((mantissa, exp)) => (
+                mantissa,
+                exp.parse::<i64>().unwrap_or(if exp.starts_with('-') {
+                    i64::MIN
+                } else {
+                    i64::MAX
+                }),
+            ),
+            None => (literal, 0),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{}{}", int, frac);
+        let digits = digits.trim_start_matches('0');
+        let significant = digits.trim_end_matches('0');
+        let trailing_zeros = (digits.len() - significant.len()) as i64;
+        Decimal {
+            negative,
+            digits: significant.to_string(),
+            exponent: exponent
+                .saturating_sub(frac.len() as i64)
+                .saturating_add(trailing_zeros),
+        }
+    }
+
+    /// exact integer value, `None` if the number has a fractional part or overflow i128,
+    /// `1.5e2` is the integer 150
+    pub fn to_i128(&self) -> Option<i128> {
+        let decimal = self.decimal();
+        let magnitude = decimal.integer_magnitude()?;
+        if decimal.negative {
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// exact integer value, `None` if the number is negative, has a fractional part or overflow u128
+    pub fn to_u128(&self) -> Option<u128> {
+        let decimal = self.decimal();
+        let magnitude = decimal.integer_magnitude()?;
+        if decimal.negative && magnitude != 0 {
+            return None;
+        }
+        Some(magnitude)
+    }
+
+    /// the nearest f64, `None` if the number is out of f64 range
+    pub fn to_f64(&self) -> Option<f64> {
+        self.0.parse::<f64>().ok().filter(|n| n.is_finite())
+    }
+
+    /// plain decimal notation without exponent, e.g. `-1.50E+3` -> `-1500` and `25e-4` -> `0.0025`,
+    /// `None` if that needs more than 4096 zeros like `1e999999999`
+    pub fn to_decimal_string(&self) -> Option<String> {
+        let Decimal {
+            negative,
+            digits,
+            exponent,
+        } = self.decimal();
+        if digits.is_empty() {
+            return Some("0".to_string());
+        }
+        let sign = if negative { "-" } else { "" };
+        let padding = |zeros: u64| {
+            usize::try_from(zeros)
+                .ok()
+                .filter(|&zeros| zeros <= MAX_PADDING_ZEROS)
+                .map(|zeros| "0".repeat(zeros))
+        };
+        if exponent >= 0 {
+            return Some(format!("{}{}{}", sign, digits, padding(exponent as u64)?));
+        }
+        let frac_len = exponent.unsigned_abs();
+        match usize::try_from(frac_len) {
+            Ok(frac_len) if digits.len() > frac_len => {
+                let (int, frac) = digits.split_at(digits.len() - frac_len);
+                Some(format!("{}{}.{}", sign, int, frac))
+            }
+            _ => Some(format!(
+                "{}0.{}{}",
+                sign,
+                padding(frac_len - digits.len() as u64)?,
+                digits
+            )),
+        }
+    }
+}
+
+impl Decimal {
+    fn integer_magnitude(&self) -> Option<u128> {
+        if self.digits.is_empty() {
+            return Some(0);
+        }
+        if self.exponent < 0 {
+            return None;
+        }
+        let mut magnitude = self.digits.parse::<u128>().ok()?;
+        for _ in 0..self.exponent {
+            magnitude = magnitude.checked_mul(10)?;
+        }
+        Some(magnitude)
+    }
+}
+
+impl FromStr for RawNumber {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match all_consuming(parse_number_literal::<JsonParseError>)(s) {
+            Ok((_, literal)) => Ok(RawNumber(literal.to_string())),
+            Err(e) => Err(JsonError::from_nom(s, e)),
+        }
+    }
+}
+
+impl fmt::Display for RawNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[test]
+fn test_raw_number() {
+    let raw = |s: &str| s.parse::<RawNumber>().unwrap();
+    let id = "170141183460469231731687303715884105727";
+    assert_eq!(raw(id).to_i128(), Some(i128::MAX));
+    assert_eq!(
+        raw("-170141183460469231731687303715884105728").to_i128(),
+        Some(i128::MIN)
+    );
+    assert_eq!(
+        raw("340282366920938463463374607431768211455").to_u128(),
+        Some(u128::MAX)
+    );
+    assert_eq!(
+        raw("340282366920938463463374607431768211456").to_u128(),
+        None
+    );
+    assert_eq!(raw("-1").to_u128(), None);
+    assert_eq!(raw("-0.0").to_u128(), Some(0));
+    assert_eq!(raw("1.5e2").to_i128(), Some(150));
+    assert_eq!(raw("1.25").to_i128(), None);
+    assert_eq!(raw("0.1").to_f64(), Some(0.1));
+    assert_eq!(raw("1e400").to_f64(), None);
+    assert_eq!(raw("19.990").to_decimal_string().unwrap(), "19.99");
+    assert_eq!(raw("-1.50E+3").to_decimal_string().unwrap(), "-1500");
+    assert_eq!(raw("25e-4").to_decimal_string().unwrap(), "0.0025");
+    assert_eq!(raw("-0.000").to_decimal_string().unwrap(), "0");
+    assert_eq!(raw("1e4096").to_decimal_string().unwrap().len(), 4097);
+    assert_eq!(raw("1e4097").to_decimal_string(), None);
+    assert_eq!(raw("1e99999999999999999999").to_decimal_string(), None);
+    assert_eq!(raw("-1e-99999999999999999999").to_decimal_string(), None);
+    assert_eq!(
+        raw("0e99999999999999999999").to_decimal_string().unwrap(),
+        "0"
+    );
+    assert_eq!(raw("0.1").to_string(), "0.1");
+    assert!("01".parse::<RawNumber>().is_err());
+    assert!("1.".parse::<RawNumber>().is_err());
+
+    let options = crate::json_parser::ParseOptions {
+        number_mode: crate::json_parser::NumberMode::ArbitraryPrecision,
+        ..Default::default()
+    };
+    let text = format!("[{}, 0.1]", id);
+    let value = crate::JsonValue::from_str_with(&text, options).unwrap();
+    assert_eq!(
+        value,
+        crate::JsonValue::Array(vec![
+            crate::JsonValue::Number(raw(id)),
+            crate::JsonValue::Number(raw("0.1")),
+        ])
+    );
+}